@@ -0,0 +1,229 @@
+//! Audit log of changes to the data file.
+//!
+//! Every command that changes the data file appends a record to `<file>.log`: a header line
+//! with the time (UTC), who ran the command and the command line, then the lines the change
+//! removed (`-`) and added (`+`). An edited entry shows up as one of each; a change that only
+//! reorders lines (e.g. `sort`) has no `-`/`+` lines. `history` reads the log back.
+//!
+//! Changes are also kept as numbered snapshots, full copies of the file in `<file>.snapshots/`,
+//! so any earlier state can be shown, compared or restored. Bulk changes additionally write a
+//! backup copy to `<file>.backups/` first, which is never pruned.
+//!
+//! ```text
+//! @<TAB>2026-10-16T09:30:12Z<TAB>alice<TAB>edit 3 2025-10-19    Email again
+//! -<TAB>2025-10-19<TAB>Email someone
+//! +<TAB>2025-10-19<TAB>Email again
+//! ```
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::sidecar_path;
+
+/// Format of the time in a record's header (UTC).
+pub const TIMESTAMP: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Path of the audit log for the data file at `data`.
+pub fn log_path(data: &Path) -> PathBuf {
+    sidecar_path(data, "log")
+}
+
+/// One change to the data file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    pub time: String,
+    pub who: String,
+    pub command: String,
+    /// Lines as they were before the change
+    pub removed: Vec<String>,
+    /// Lines as they are after the change
+    pub added: Vec<String>,
+}
+
+/// The lines of `before` that are missing from `after`, and those of `after` missing from
+/// `before`, each in file order. A line that occurs several times counts as often as it occurs.
+pub fn diff(before: &str, after: &str) -> (Vec<String>, Vec<String>) {
+    let missing = |from: &str, other: &str| {
+        let mut left: HashMap<&str, usize> = HashMap::new();
+        for line in other.lines() {
+            *left.entry(line).or_default() += 1;
+        }
+        let mut out = Vec::new();
+        for line in from.lines() {
+            match left.get_mut(line) {
+                Some(n) if *n > 0 => *n -= 1,
+                _ => out.push(line.to_string()),
+            }
+        }
+        out
+    };
+    (missing(before, after), missing(after, before))
+}
+
+/// Append a record of the data file at `data` changing from `before` to `after`, made at `time`
+/// by `who` running `command`. Nothing is written when the content didn't change.
+pub fn record(data: &Path, before: &str, after: &str, time: DateTime<Utc>, who: &str, command: &str) -> io::Result<()> {
+    if before == after {
+        return Ok(());
+    }
+    let (removed, added) = diff(before, after);
+    let clean = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+    let mut text = format!("@\t{}\t{}\t{}\n", time.format(TIMESTAMP), clean(who), clean(command));
+    for line in &removed {
+        text.push_str(&format!("-\t{}\n", line));
+    }
+    for line in &added {
+        text.push_str(&format!("+\t{}\n", line));
+    }
+    let mut f = OpenOptions::new().create(true).append(true).open(log_path(data))?;
+    f.write_all(text.as_bytes())
+}
+
+/// All records in the audit log of `data`, oldest first. A missing log has none.
+pub fn read(data: &Path) -> io::Result<Vec<Record>> {
+    let content = match std::fs::read_to_string(log_path(data)) {
+        Ok(c) => c,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut records: Vec<Record> = Vec::new();
+    for line in content.lines() {
+        let Some((kind, rest)) = line.split_once('\t') else {
+            continue;
+        };
+        match (kind, records.last_mut()) {
+            ("@", _) => {
+                let mut fields = rest.splitn(3, '\t');
+                records.push(Record {
+                    time: fields.next().unwrap_or("").to_string(),
+                    who: fields.next().unwrap_or("").to_string(),
+                    command: fields.next().unwrap_or("").to_string(),
+                    ..Record::default()
+                });
+            }
+            ("-", Some(r)) => r.removed.push(rest.to_string()),
+            ("+", Some(r)) => r.added.push(rest.to_string()),
+            _ => {}
+        }
+    }
+    Ok(records)
+}
+
+/// When each line of the data file was last written, according to the audit log: the day (UTC)
+/// of the latest record adding it. Also returns the day of the first record, before which
+/// nothing is known.
+pub fn last_written(data: &Path) -> io::Result<(HashMap<String, NaiveDate>, Option<NaiveDate>)> {
+    let day = |time: &str| NaiveDateTime::parse_from_str(time, TIMESTAMP).ok().map(|t| t.date());
+    let records = read(data)?;
+    let mut written = HashMap::new();
+    for r in &records {
+        if let Some(day) = day(&r.time) {
+            for line in &r.added {
+                written.insert(line.clone(), day);
+            }
+        }
+    }
+    Ok((written, records.first().and_then(|r| day(&r.time))))
+}
+
+/// Format of the time in a snapshot's file name (UTC).
+const SNAPSHOT_TIME: &str = "%Y%m%dT%H%M%SZ";
+
+/// Directory holding the snapshots of the data file at `data`.
+pub fn snapshot_dir(data: &Path) -> PathBuf {
+    sidecar_path(data, "snapshots")
+}
+
+/// A saved copy of the data file, stored as `<number>-<time>.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Increases with every snapshot and is never reused
+    pub number: u32,
+    /// When it was taken, formatted like [`TIMESTAMP`]
+    pub time: String,
+    pub path: PathBuf,
+}
+
+/// The snapshots of `data`, oldest first. Without a snapshot directory there are none.
+pub fn snapshots(data: &Path) -> io::Result<Vec<Snapshot>> {
+    let dir = match std::fs::read_dir(snapshot_dir(data)) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut out = Vec::new();
+    for item in dir {
+        let path = item?.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some((number, time)) = stem.split_once('-') else {
+            continue;
+        };
+        let (Ok(number), Ok(time)) = (number.parse(), NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME)) else {
+            continue;
+        };
+        out.push(Snapshot { number, time: time.format(TIMESTAMP).to_string(), path });
+    }
+    out.sort_by_key(|s| s.number);
+    Ok(out)
+}
+
+/// The content of snapshot `number` of `data`.
+pub fn read_snapshot(data: &Path, number: u32) -> io::Result<String> {
+    let snapshot = snapshots(data)?.into_iter().find(|s| s.number == number).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("no snapshot {} of {}", number, data.display()))
+    })?;
+    std::fs::read_to_string(snapshot.path)
+}
+
+/// Save `content` as a new snapshot of `data` taken at `time` and return its number. Nothing is
+/// saved (and `None` returned) when it equals the latest snapshot.
+pub fn take_snapshot(data: &Path, content: &str, time: DateTime<Utc>) -> io::Result<Option<u32>> {
+    let existing = snapshots(data)?;
+    if let Some(latest) = existing.last()
+        && std::fs::read_to_string(&latest.path).is_ok_and(|c| c == content)
+    {
+        return Ok(None);
+    }
+    let number = existing.last().map_or(1, |s| s.number + 1);
+    let dir = snapshot_dir(data);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(format!("{:06}-{}.txt", number, time.format(SNAPSHOT_TIME))), content)?;
+    Ok(Some(number))
+}
+
+/// Delete all but the `keep` most recent snapshots of `data`; returns how many were deleted.
+pub fn prune_snapshots(data: &Path, keep: usize) -> io::Result<usize> {
+    let existing = snapshots(data)?;
+    let excess = existing.len().saturating_sub(keep);
+    for s in &existing[..excess] {
+        std::fs::remove_file(&s.path)?;
+    }
+    Ok(excess)
+}
+
+/// Directory holding the backups of the data file at `data`.
+pub fn backup_dir(data: &Path) -> PathBuf {
+    sidecar_path(data, "backups")
+}
+
+/// Copy the data file at `data` to a new backup named after `time` and return its path.
+/// Backups are kept until deleted by hand.
+pub fn backup(data: &Path, time: DateTime<Utc>) -> io::Result<PathBuf> {
+    let dir = backup_dir(data);
+    std::fs::create_dir_all(&dir)?;
+    let stamp = time.format(SNAPSHOT_TIME).to_string();
+    let mut path = dir.join(format!("{}.txt", stamp));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = dir.join(format!("{}-{}.txt", stamp, n));
+    }
+    std::fs::copy(data, &path)?;
+    Ok(path)
+}
@@ -0,0 +1,57 @@
+//! `board`: a kanban view that renders one column per status side by side, sized to the terminal.
+
+use std::fmt::Write as _;
+
+use iron_list::Entry;
+use iron_list::dates;
+
+use crate::wrap_text;
+
+/// Space between two columns.
+const GAP: usize = 3;
+/// Narrowest a column gets, however many there are.
+const MIN_WIDTH: usize = 12;
+
+pub struct Column<'a, 'e> {
+    pub title: String,
+    pub entries: Vec<&'a Entry<'e>>,
+}
+
+/// The lines of one column: its title, an underline, then each entry's date and wrapped
+/// description, separated by blank lines.
+fn column_lines(column: &Column<'_, '_>, width: usize) -> Vec<String> {
+    let mut lines = wrap_text(&column.title, width);
+    lines.push("-".repeat(width));
+    for (i, e) in column.entries.iter().enumerate() {
+        if i > 0 {
+            lines.push(String::new());
+        }
+        lines.push(dates::display(e.date));
+        lines.extend(wrap_text(&e.desc, width));
+    }
+    lines
+}
+
+/// Render `columns` side by side, sharing `total_width` terminal columns between them.
+pub fn render(columns: &[Column<'_, '_>], total_width: usize) -> String {
+    let mut out = String::new();
+    if columns.is_empty() {
+        return out;
+    }
+    let gaps = GAP * (columns.len() - 1);
+    let width = (total_width.saturating_sub(gaps) / columns.len()).max(MIN_WIDTH);
+    let cells: Vec<Vec<String>> = columns.iter().map(|c| column_lines(c, width)).collect();
+    let rows = cells.iter().map(Vec::len).max().unwrap_or(0);
+    for row in 0..rows {
+        let mut line = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 {
+                line.push_str(&" ".repeat(GAP));
+            }
+            let text = cell.get(row).map_or("", String::as_str);
+            write!(line, "{:width$}", text, width = width).ok();
+        }
+        writeln!(out, "{}", line.trim_end()).ok();
+    }
+    out
+}
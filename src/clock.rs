@@ -0,0 +1,104 @@
+//! What "today" is, in the configured time zone.
+//!
+//! The `timezone` setting picks the zone used to decide which entries are due or overdue:
+//! `local` (the system zone, the default), `UTC` or a fixed offset such as `+02:00`. A single
+//! entry can carry its own zone as a `tz:<zone>` tag, e.g. for a deadline in another office.
+
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
+
+use crate::Entry;
+
+/// A time zone for computing the current date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Parse `local`, `UTC` or an offset `+HH`, `+HH:MM` or `+HHMM` (also with `-`, and
+    /// optionally prefixed with `UTC`).
+    pub fn parse(s: &str) -> Option<Zone> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("local") {
+            return Some(Zone::Local);
+        }
+        let offset = s
+            .strip_prefix("UTC")
+            .or_else(|| s.strip_prefix("utc"))
+            .unwrap_or(s);
+        if offset.is_empty() || offset == "Z" {
+            return Some(Zone::Fixed(FixedOffset::east_opt(0)?));
+        }
+        let (sign, digits) = match offset.as_bytes()[0] {
+            b'+' => (1, &offset[1..]),
+            b'-' => (-1, &offset[1..]),
+            _ => return None,
+        };
+        let digits = digits.replace(':', "");
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (hours, minutes): (i32, i32) = match digits.len() {
+            1 | 2 => (digits.parse().ok()?, 0),
+            4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+            _ => return None,
+        };
+        if hours > 14 || minutes > 59 {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Zone::Fixed)
+    }
+
+    /// The date in this zone at the instant `now`.
+    pub fn date_at(self, now: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Zone::Local => now.with_timezone(&Local).date_naive(),
+            Zone::Fixed(offset) => now.with_timezone(&offset).date_naive(),
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Local => write!(f, "local"),
+            Zone::Fixed(offset) if offset.local_minus_utc() == 0 => write!(f, "UTC"),
+            Zone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+/// The zone an entry carries in a `tz:<zone>` tag, if any.
+pub fn entry_zone(e: &Entry<'_>) -> Option<Zone> {
+    e.tags
+        .iter()
+        .find_map(|t| t.strip_prefix("tz:"))
+        .and_then(Zone::parse)
+}
+
+/// A fixed instant together with the configured zone, so that every "today" computed during one
+/// command agrees.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    pub now: DateTime<Utc>,
+    pub zone: Zone,
+}
+
+impl Clock {
+    pub fn new(zone: Zone) -> Clock {
+        Clock { now: Utc::now(), zone }
+    }
+
+    /// Today's date in the configured zone.
+    pub fn today(&self) -> NaiveDate {
+        self.zone.date_at(self.now)
+    }
+
+    /// Today's date as far as `e` is concerned: in its own `tz:` zone when it has one.
+    pub fn today_for(&self, e: &Entry<'_>) -> NaiveDate {
+        entry_zone(e).unwrap_or(self.zone).date_at(self.now)
+    }
+}
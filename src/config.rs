@@ -0,0 +1,307 @@
+//! Persistent settings.
+//!
+//! Settings live in `.ironlist_config` in the user's home directory (falling back to the
+//! current directory, like `.ironlist_default`). The file holds one `key = value` pair per
+//! line; blank lines and lines starting with `#` are ignored.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::clock::Zone;
+use crate::dates;
+use crate::i18n::Lang;
+
+const FILE_NAME: &str = ".ironlist_config";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Keep the data file sorted by date: `add` inserts at the matching date position and edits
+    /// that change a date move the line.
+    pub sorted_writes: bool,
+    /// Pipe long `list`/`query` output through a pager when writing to a terminal.
+    pub paging: bool,
+    /// Language of the output; `None` (`auto`) follows the environment's locale.
+    pub lang: Option<Lang>,
+    /// How dates are shown and typed, e.g. `DD.MM.YYYY`. The data file always stores ISO dates.
+    pub date_format: String,
+    /// Time zone that decides what "today" is.
+    pub timezone: Zone,
+    /// Move overdue open entries to today before running any command that shows entries.
+    pub auto_roll: bool,
+    /// Tag rolled entries with `rolled:<n>`, counting how often each was moved.
+    pub roll_mark: bool,
+    /// Active context: when non-empty only entries with at least one of these tags are shown.
+    pub context: Vec<String>,
+    /// Warn when `start` leaves more than this many entries in progress (0 never warns).
+    pub max_in_progress: usize,
+    /// Most open entries one day should hold; `add`, `reschedule` and `plan` warn beyond it
+    /// (0 sets no limit).
+    pub day_capacity: usize,
+    /// Most hours of estimated work (`est:` tags) one day should hold (0 sets no limit).
+    pub day_hours: f64,
+    /// Refuse every command that would change the data file, e.g. for a shared or synced file.
+    pub read_only: bool,
+    /// Record who adds (`by:`) and completes (`done-by:`) entries, for lists shared by a family
+    /// or team.
+    pub attribution: bool,
+    /// The name recorded by `attribution`; empty uses `$USER` (`$USERNAME` on Windows).
+    pub author: String,
+    /// How many snapshots of the data file to keep (0 turns automatic snapshots off).
+    pub snapshot_keep: usize,
+    /// Days deleted entries stay in the trash file before they are purged (0 keeps them).
+    pub trash_retention_days: u32,
+    /// Days after which completed entries are moved to the archive file whenever the data
+    /// file is changed (0 turns this off).
+    pub auto_archive_days: u32,
+    /// Fewest due entries `prompt` shows a segment for.
+    pub prompt_threshold: usize,
+    /// Days ahead `prompt` counts entries as due (0 counts today and overdue ones only).
+    pub prompt_days: u32,
+    /// More files whose entries `list`, `query`, `count` and `complete` show alongside the data
+    /// file's.
+    pub files: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            sorted_writes: false,
+            paging: true,
+            lang: None,
+            date_format: "YYYY-MM-DD".to_string(),
+            timezone: Zone::Local,
+            auto_roll: false,
+            roll_mark: false,
+            context: Vec::new(),
+            max_in_progress: 3,
+            day_capacity: 0,
+            day_hours: 0.0,
+            read_only: false,
+            attribution: false,
+            author: String::new(),
+            snapshot_keep: 20,
+            trash_retention_days: 0,
+            auto_archive_days: 0,
+            prompt_threshold: 1,
+            prompt_days: 0,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// Names of all recognized settings, in the order `config show` prints them.
+pub const KEYS: &[&str] = &["sorted_writes", "paging", "lang", "date_format", "timezone", "auto_roll", "roll_mark", "context", "max_in_progress", "day_capacity", "day_hours", "read_only", "attribution", "author", "snapshot_keep", "trash_retention_days", "auto_archive_days", "prompt_threshold", "prompt_days", "files"];
+
+/// Candidate config file locations, most preferred first.
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(FILE_NAME));
+    }
+    paths.push(PathBuf::from(FILE_NAME));
+    paths
+}
+
+/// The config file in use: the first candidate that exists, or the preferred location.
+pub fn config_path() -> PathBuf {
+    let paths = config_paths();
+    paths
+        .iter()
+        .find(|p| p.exists())
+        .cloned()
+        .unwrap_or_else(|| paths[0].clone())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("expected true or false, got `{}`", value)),
+    }
+}
+
+impl Config {
+    /// Load settings from the config file. A missing file yields the defaults; unknown keys and
+    /// invalid values are reported on stderr and ignored.
+    pub fn load() -> Config {
+        let mut config = Config::default();
+        let Ok(content) = std::fs::read_to_string(config_path()) else {
+            return config;
+        };
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("Ignoring malformed config line {}: {}", i + 1, line);
+                continue;
+            };
+            if let Err(msg) = config.apply(key.trim(), value.trim()) {
+                eprintln!("Ignoring config line {}: {}", i + 1, msg);
+            }
+        }
+        config
+    }
+
+    /// Set one setting from its textual form.
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "sorted_writes" => self.sorted_writes = parse_bool(value)?,
+            "paging" => self.paging = parse_bool(value)?,
+            "auto_roll" => self.auto_roll = parse_bool(value)?,
+            "roll_mark" => self.roll_mark = parse_bool(value)?,
+            "read_only" => self.read_only = parse_bool(value)?,
+            "attribution" => self.attribution = parse_bool(value)?,
+            "author" => {
+                if value.contains([',', '\t']) {
+                    return Err(format!("names can't contain commas or tabs, got `{}`", value));
+                }
+                self.author = value.to_string();
+            }
+            "max_in_progress" => {
+                self.max_in_progress = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "day_capacity" => {
+                self.day_capacity = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "day_hours" => {
+                self.day_hours = value
+                    .parse()
+                    .ok()
+                    .filter(|h: &f64| h.is_finite() && *h >= 0.0)
+                    .ok_or_else(|| format!("expected a number of hours, got `{}`", value))?
+            }
+            "snapshot_keep" => {
+                self.snapshot_keep = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "trash_retention_days" => {
+                self.trash_retention_days = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "auto_archive_days" => {
+                self.auto_archive_days = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "prompt_threshold" => {
+                self.prompt_threshold = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "prompt_days" => {
+                self.prompt_days = value
+                    .parse()
+                    .map_err(|_| format!("expected a whole number, got `{}`", value))?
+            }
+            "files" => {
+                self.files = value.split(',').map(str::trim).filter(|f| !f.is_empty()).map(PathBuf::from).collect()
+            }
+            "context" => {
+                self.context = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+            "lang" => {
+                self.lang = if value.eq_ignore_ascii_case("auto") {
+                    None
+                } else {
+                    let codes: Vec<&str> = Lang::ALL.iter().map(|l| l.code()).collect();
+                    Some(Lang::parse(value).ok_or_else(|| {
+                        format!("expected auto or one of {}, got `{}`", codes.join(", "), value)
+                    })?)
+                }
+            }
+            "date_format" => {
+                dates::pattern_to_strftime(value)?;
+                self.date_format = value.to_string();
+            }
+            "timezone" => {
+                self.timezone = Zone::parse(value)
+                    .ok_or_else(|| format!("expected local, UTC or an offset like +02:00, got `{}`", value))?
+            }
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// The textual value of a setting, as written to the config file.
+    pub fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "sorted_writes" => Some(self.sorted_writes.to_string()),
+            "paging" => Some(self.paging.to_string()),
+            "auto_roll" => Some(self.auto_roll.to_string()),
+            "roll_mark" => Some(self.roll_mark.to_string()),
+            "context" => Some(self.context.join(",")),
+            "files" => Some(self.files.iter().map(|f| f.display().to_string()).collect::<Vec<_>>().join(",")),
+            "max_in_progress" => Some(self.max_in_progress.to_string()),
+            "day_capacity" => Some(self.day_capacity.to_string()),
+            "day_hours" => Some(self.day_hours.to_string()),
+            "read_only" => Some(self.read_only.to_string()),
+            "attribution" => Some(self.attribution.to_string()),
+            "author" => Some(self.author.clone()),
+            "snapshot_keep" => Some(self.snapshot_keep.to_string()),
+            "trash_retention_days" => Some(self.trash_retention_days.to_string()),
+            "auto_archive_days" => Some(self.auto_archive_days.to_string()),
+            "prompt_threshold" => Some(self.prompt_threshold.to_string()),
+            "prompt_days" => Some(self.prompt_days.to_string()),
+            "date_format" => Some(self.date_format.clone()),
+            "timezone" => Some(self.timezone.to_string()),
+            "lang" => Some(self.lang.map_or("auto", Lang::code).to_string()),
+            _ => None,
+        }
+    }
+
+    /// The name to attribute additions and completions to, when `attribution` is on.
+    pub fn author(&self) -> Option<String> {
+        self.attribution.then(|| self.user()).flatten()
+    }
+
+    /// Who is running the command: the `author` setting, else `$USER` (`$USERNAME` on Windows).
+    pub fn user(&self) -> Option<String> {
+        Some(self.author.clone())
+            .filter(|a| !a.is_empty())
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .map(|a| a.replace([',', '\t'], " ").trim().to_string())
+            .filter(|a| !a.is_empty())
+    }
+}
+
+/// Validate `key = value` and write it to the config file, replacing an existing line for the
+/// same key and leaving every other line untouched.
+pub fn set(key: &str, value: &str) -> io::Result<()> {
+    Config::default()
+        .apply(key, value)
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+
+    let path = config_path();
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    let new_line = format!("{} = {}", key, value);
+    let current = lines.iter().position(|l| {
+        l.split_once('=')
+            .is_some_and(|(k, _)| k.trim() == key && !l.trim_start().starts_with('#'))
+    });
+    match current {
+        Some(i) => lines[i] = new_line,
+        None => lines.push(new_line),
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    std::fs::write(path, content)
+}
@@ -0,0 +1,151 @@
+//! The user's preferred date format for display and input.
+//!
+//! The format is written as a pattern such as `DD.MM.YYYY` (the `date_format` setting) and
+//! selected once at startup with [`set_format`]. Dates are shown in it and accepted in it as well
+//! as in ISO `YYYY-MM-DD`. The data file always stores ISO dates.
+
+use std::sync::OnceLock;
+
+use chrono::{Datelike, NaiveDate};
+
+/// The format dates are stored in.
+pub const ISO: &str = "%Y-%m-%d";
+
+/// Stands in for the date of an entry that has none (written `-` in the data file). It sorts
+/// after every real date and never falls inside a date range.
+pub const UNDATED: NaiveDate = NaiveDate::MAX;
+
+/// The selected pattern and its `strftime` translation.
+static FORMAT: OnceLock<(String, String)> = OnceLock::new();
+
+/// Translate a pattern made of `YYYY`, `YY`, `MM` and `DD` plus literal separators into a
+/// `strftime` format.
+pub fn pattern_to_strftime(pattern: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    let (mut year, mut month, mut day) = (false, false, false);
+    while let Some(c) = rest.chars().next() {
+        let (spec, len) = if rest.starts_with("YYYY") {
+            year = true;
+            ("%Y", 4)
+        } else if rest.starts_with("YY") {
+            year = true;
+            ("%y", 2)
+        } else if rest.starts_with("MM") {
+            month = true;
+            ("%m", 2)
+        } else if rest.starts_with("DD") {
+            day = true;
+            ("%d", 2)
+        } else if c.is_ascii_alphanumeric() || c == '%' {
+            return Err(format!("unexpected `{}` in date format `{}`; use YYYY, YY, MM and DD", c, pattern));
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        out.push_str(spec);
+        rest = &rest[len..];
+    }
+    if !(year && month && day) {
+        return Err(format!("date format `{}` needs a year, a month and a day", pattern));
+    }
+    Ok(out)
+}
+
+/// Use `pattern` (see [`pattern_to_strftime`]) for display and input. Only the first call has
+/// an effect.
+pub fn set_format(pattern: &str) -> Result<(), String> {
+    FORMAT.set((pattern.to_string(), pattern_to_strftime(pattern)?)).ok();
+    Ok(())
+}
+
+fn format_str() -> &'static str {
+    FORMAT.get().map_or(ISO, |(_, f)| f.as_str())
+}
+
+/// The display format as a pattern, for prompts.
+pub fn pattern() -> &'static str {
+    FORMAT.get().map_or("YYYY-MM-DD", |(p, _)| p.as_str())
+}
+
+/// `date` in the display format, `-` for [`UNDATED`].
+pub fn display(date: NaiveDate) -> String {
+    if date == UNDATED {
+        return "-".to_string();
+    }
+    date.format(format_str()).to_string()
+}
+
+/// `date` as the data file stores it: ISO, `-` for [`UNDATED`].
+pub fn store(date: NaiveDate) -> String {
+    if date == UNDATED {
+        return "-".to_string();
+    }
+    date.format(ISO).to_string()
+}
+
+/// Parse a date as the data file stores it (see [`store`]).
+pub fn parse_stored(s: &str) -> Option<NaiveDate> {
+    match s.trim() {
+        "-" => Some(UNDATED),
+        s => NaiveDate::parse_from_str(s, ISO).ok(),
+    }
+}
+
+/// Parse a date typed by the user, in the display format or in ISO format.
+pub fn parse_input(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    NaiveDate::parse_from_str(s, format_str())
+        .or_else(|_| NaiveDate::parse_from_str(s, ISO))
+        .ok()
+}
+
+/// Resolve a date keyword relative to `today`: `today`, `tomorrow`, `yesterday`, a weekday name
+/// such as `friday` or `fri` (the next such day, today included; `next friday` excludes today),
+/// or an offset such as `+3d`, `-2w` or `+1m`.
+pub fn parse_keyword(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let s = s.trim().to_ascii_lowercase();
+    match s.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        _ => {}
+    }
+    if s.starts_with(['+', '-']) && s.len() > 2 && s.is_char_boundary(s.len() - 1) {
+        let (count, unit) = s.split_at(s.len() - 1);
+        let count: i64 = count.parse().ok()?;
+        return match unit {
+            "d" => today.checked_add_signed(chrono::Duration::days(count)),
+            "w" => today.checked_add_signed(chrono::Duration::weeks(count)),
+            "m" if count >= 0 => today.checked_add_months(chrono::Months::new(count as u32)),
+            "m" => today.checked_sub_months(chrono::Months::new(count.unsigned_abs() as u32)),
+            _ => None,
+        };
+    }
+    let (name, skip_today) = match s.strip_prefix("next ") {
+        Some(name) => (name.trim(), true),
+        None => (s.as_str(), false),
+    };
+    let weekday: chrono::Weekday = name.parse().ok()?;
+    let mut ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    if ahead == 0 && skip_today {
+        ahead = 7;
+    }
+    today.checked_add_signed(chrono::Duration::days(ahead.into()))
+}
+
+/// Parse a date typed by the user that may also be a keyword relative to `today` (see
+/// [`parse_keyword`]).
+pub fn parse_relative(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    parse_keyword(s, today).or_else(|| parse_input(s))
+}
+
+/// The date an age such as `7d`, `2w` or `3m` ago, counted back from `today`.
+pub fn ago(age: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let age = age.trim();
+    if age.starts_with(['+', '-']) {
+        return None;
+    }
+    parse_keyword(&format!("-{}", age), today)
+}
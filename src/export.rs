@@ -0,0 +1,221 @@
+//! Rendering entries into other formats for `export`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::clock::Clock;
+use crate::import::external_id;
+use crate::{Entry, annotations, dates, is_active, status};
+
+/// Escape text for inclusion in HTML element content or attribute values.
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn html_section(out: &mut String, title: &str, entries: &[&Entry<'_>], show_dates: bool) {
+    writeln!(out, "<h2>{}</h2>", title).ok();
+    if entries.is_empty() {
+        writeln!(out, "<p class=\"empty\">Nothing here.</p>").ok();
+        return;
+    }
+    writeln!(out, "<ul>").ok();
+    for e in entries {
+        let date = if show_dates {
+            format!("<span class=\"date\">{}</span> ", dates::display(e.date))
+        } else {
+            String::new()
+        };
+        let tags = if e.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" <span class=\"tags\">{}</span>", html_escape(&e.tags.join(", ")))
+        };
+        writeln!(out, "<li><span class=\"box\"></span>{}{}{}</li>", date, html_escape(&e.desc), tags).ok();
+    }
+    writeln!(out, "</ul>").ok();
+}
+
+/// A one-page, print-friendly HTML agenda for today: open entries due today and open overdue
+/// entries, each with a checkbox to tick off on paper, plus a few blank lines for notes.
+pub fn daily_sheet_html(entries: &[Entry<'_>], clock: &Clock) -> String {
+    let open: Vec<&Entry<'_>> = entries.iter().filter(|e| is_active(e)).collect();
+    let overdue: Vec<&Entry<'_>> = open.iter().copied().filter(|e| e.date < clock.today_for(e)).collect();
+    let due_today: Vec<&Entry<'_>> = open.iter().copied().filter(|e| e.date == clock.today_for(e)).collect();
+    let today = clock.today();
+
+    let mut out = String::new();
+    let title = format!("IronList — {}, {}", today.format("%A"), dates::display(today));
+    writeln!(out, "<!DOCTYPE html>").ok();
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").ok();
+    writeln!(out, "<title>{}</title>", html_escape(&title)).ok();
+    writeln!(
+        out,
+        "<style>
+@page {{ size: auto; margin: 15mm; }}
+body {{ font-family: Georgia, serif; max-width: 40em; margin: 2em auto; color: #000; }}
+h1 {{ font-size: 1.5em; border-bottom: 2px solid #000; padding-bottom: .2em; }}
+h2 {{ font-size: 1.1em; margin-top: 1.5em; }}
+ul {{ list-style: none; padding: 0; }}
+li {{ margin: .5em 0; break-inside: avoid; }}
+.box {{ display: inline-block; width: .9em; height: .9em; border: 1px solid #000; margin-right: .6em; vertical-align: -.1em; }}
+.date {{ font-variant-numeric: tabular-nums; margin-right: .4em; }}
+.tags {{ color: #555; font-size: .85em; }}
+.empty {{ color: #555; font-style: italic; }}
+.notes div {{ border-bottom: 1px solid #999; height: 2em; }}
+@media print {{ body {{ margin: 0; }} }}
+</style>
+</head>
+<body>"
+    )
+    .ok();
+    writeln!(out, "<h1>{}</h1>", html_escape(&title)).ok();
+    html_section(&mut out, "Today", &due_today, false);
+    html_section(&mut out, "Overdue", &overdue, true);
+    writeln!(out, "<h2>Notes</h2>\n<div class=\"notes\">").ok();
+    for _ in 0..5 {
+        writeln!(out, "<div></div>").ok();
+    }
+    writeln!(out, "</div>\n</body>\n</html>").ok();
+    out
+}
+
+/// Escape text for inclusion in a JSON string.
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).ok();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// All entries as a JSON array of objects with `date` (ISO, `null` when undated), `description`, `status`, `tags` and
+/// `annotations` (each with `time` and `text`).
+pub fn entries_json(entries: &[Entry<'_>]) -> String {
+    let mut out = String::from("[\n");
+    for (i, e) in entries.iter().enumerate() {
+        let tags: Vec<String> = e
+            .tags
+            .iter()
+            .filter(|t| !t.starts_with("note:"))
+            .map(|t| format!("\"{}\"", json_escape(t)))
+            .collect();
+        let notes: Vec<String> = annotations(e)
+            .iter()
+            .map(|(time, text)| format!("{{\"time\": \"{}\", \"text\": \"{}\"}}", json_escape(time), json_escape(text)))
+            .collect();
+        write!(
+            out,
+            "  {{\"date\": {}, \"description\": \"{}\", \"status\": \"{}\", \"tags\": [{}], \"annotations\": [{}]}}",
+            if e.date == dates::UNDATED { "null".to_string() } else { format!("\"{}\"", dates::store(e.date)) },
+            json_escape(&e.desc),
+            status(e).name(),
+            tags.join(", "),
+            notes.join(", ")
+        )
+        .ok();
+        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Escape text for an iCalendar TEXT value.
+fn ics_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Fold an iCalendar content line at 75 octets (never inside a character) and end it with CRLF.
+fn ics_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// 64-bit FNV-1a hash of `bytes`. Unlike the standard library's hasher it is fixed, so UIDs made
+/// from it stay the same across builds and calendar apps don't see every event replaced.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Open dated entries as an iCalendar feed of all-day events on their dates, for calendar apps
+/// to subscribe to. An entry's UID comes from its `id:` tag, or else from the FNV-1a hash of its
+/// description, so it stays the same when the entry is rescheduled.
+pub fn entries_ics(entries: &[Entry<'_>], now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    ics_line(&mut out, "BEGIN:VCALENDAR");
+    ics_line(&mut out, "VERSION:2.0");
+    ics_line(&mut out, "PRODID:-//IronList//IronList//EN");
+    ics_line(&mut out, "X-WR-CALNAME:IronList");
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    // entries with the same description get numbered UIDs, in file order
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for e in entries.iter().filter(|e| is_active(e) && e.date != dates::UNDATED) {
+        let uid = match external_id(e) {
+            Some(id) => format!("{}@iron-list", id),
+            None => {
+                let n = seen.entry(&e.desc).or_default();
+                *n += 1;
+                format!("{:016x}-{}@iron-list", fnv1a(e.desc.as_bytes()), n)
+            }
+        };
+        ics_line(&mut out, "BEGIN:VEVENT");
+        ics_line(&mut out, &format!("UID:{}", ics_escape(&uid)));
+        ics_line(&mut out, &format!("DTSTAMP:{}", stamp));
+        ics_line(&mut out, &format!("DTSTART;VALUE=DATE:{}", e.date.format("%Y%m%d")));
+        ics_line(&mut out, &format!("DTEND;VALUE=DATE:{}", (e.date + Duration::days(1)).format("%Y%m%d")));
+        ics_line(&mut out, &format!("SUMMARY:{}", ics_escape(&e.desc)));
+        let categories: Vec<String> = e.tags.iter().filter(|t| !t.contains(':')).map(|t| ics_escape(t)).collect();
+        if !categories.is_empty() {
+            ics_line(&mut out, &format!("CATEGORIES:{}", categories.join(",")));
+        }
+        ics_line(&mut out, "TRANSP:TRANSPARENT");
+        ics_line(&mut out, "END:VEVENT");
+    }
+    ics_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ics_uids_are_fixed_hashes_of_the_description() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        let entries = crate::parse_entries("2026-10-21\tCall the bank\n2026-10-22\tCall the bank\n", crate::FileFormat::Tsv);
+        let ics = entries_ics(&entries, DateTime::from_timestamp(0, 0).unwrap());
+        let uid = format!("{:016x}", fnv1a(b"Call the bank"));
+        assert!(ics.contains(&format!("UID:{}-1@iron-list\r\n", uid)), "{}", ics);
+        assert!(ics.contains(&format!("UID:{}-2@iron-list\r\n", uid)), "{}", ics);
+    }
+}
@@ -0,0 +1,652 @@
+//! Translated user-facing text.
+//!
+//! Every translatable string is a [`Msg`]; [`tr`] looks it up in the current language and [`trf`]
+//! fills in `{}` placeholders. The language is chosen once at startup with [`set_lang`] (from
+//! `--lang`, the `lang` setting or the environment) and defaults to English.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// A bundled locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    De,
+    Fr,
+}
+
+impl Lang {
+    pub const ALL: &[Lang] = &[Lang::En, Lang::De, Lang::Fr];
+
+    /// The language code used by `--lang` and the `lang` setting.
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::De => "de",
+            Lang::Fr => "fr",
+        }
+    }
+
+    /// Parse a language code. Locale names such as `de_DE.UTF-8` or `fr-CA` match on the language
+    /// part.
+    pub fn parse(s: &str) -> Option<Lang> {
+        let code = s.split(['_', '-', '.']).next().unwrap_or("").to_ascii_lowercase();
+        Lang::ALL.iter().copied().find(|l| l.code() == code)
+    }
+
+    /// The language of the environment's locale (`LC_ALL`, `LC_MESSAGES`, then `LANG`), if bundled.
+    pub fn from_env() -> Option<Lang> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|v| Lang::parse(&v))
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Select the output language. Only the first call has an effect.
+pub fn set_lang(lang: Lang) {
+    LANG.set(lang).ok();
+}
+
+/// The output language selected with [`set_lang`], English if none was.
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Msg {
+    ColNo,
+    ColDate,
+    ColTask,
+    ColTags,
+    CompletedHeading,
+    NothingToDo,
+    NoMatchingOpen,
+    IndexOutOfRange,
+    QueryNeedsCriterion,
+    MalformedLine,
+    MalformedReplacement,
+    InsertedEntry,
+    AppendedEntry,
+    ReplacedEntry,
+    CompletedEntry,
+    ExportedTo,
+    SortedFile,
+    IndexedEntries,
+    RemovedIndex,
+    NoIndex,
+    NothingToReview,
+    EntriesToReview,
+    Overdue,
+    Undated,
+    Untouched,
+    TagsLabel,
+    ReviewActions,
+    ReviewNewDate,
+    InvalidDate,
+    ReviewAnswerHint,
+    NoChanges,
+    ReviewSaved,
+    NextOccurrence,
+    RolledEntries,
+    NoContext,
+    ActiveContext,
+    ColProject,
+    ColOpen,
+    ColDone,
+    NoProject,
+    Backlog,
+    InProgress,
+    Done,
+    StatusSet,
+    TooManyInProgress,
+    WhereNeedsCriterion,
+    FiltersNeedWhere,
+    MalformedSubstitution,
+    NothingToReplace,
+    ApplyChanges,
+    EditedEntries,
+    RetagNeedsCriterion,
+    InvalidTag,
+    RetaggedEntries,
+    MoveNeedsSelection,
+    MoveToSameFile,
+    MovedEntries,
+    SplitTargetExists,
+    SplitFile,
+    ImportedEntries,
+    ImportMerged,
+    DownloadFailed,
+    NothingToImport,
+    ReadOnly,
+    RemoteSidecar,
+    ColPerson,
+    Unattributed,
+    NoHistory,
+    NoSnapshots,
+    ColTime,
+    ColEntries,
+    NoSuchSnapshot,
+    NoDifferences,
+    RestoredSnapshot,
+    SnapshotTaken,
+    SnapshotUnchanged,
+    OnlyIn,
+    Differing,
+    BackupSaved,
+    InvalidAge,
+    PurgedTrash,
+    PrunedEntries,
+    ColStatus,
+    ColRepeats,
+    AfterCompletion,
+    ColLine,
+    ColRaw,
+    NoLinks,
+    WhichLink,
+    NoSuchLink,
+    Annotated,
+    ColNote,
+    NotRepeating,
+    SkippedOccurrence,
+    Rescheduled,
+    NothingToPlan,
+    Planned,
+    OverCapacity,
+    OverCapacityHours,
+    NoDateHeading,
+    Activated,
+    Captured,
+    InboxEmpty,
+    Filed,
+    Dropped,
+    NothingDueForReview,
+    StatsOpen,
+    StatsClosed,
+    DonePerDay,
+    DonePerWeek,
+    DonePerMonth,
+    DoneAverage,
+    ScheduledAverage,
+    NoCompletionDates,
+    OverdueAging,
+    NothingStale,
+    ColTouched,
+    ColDays,
+    NoChangeHistory,
+    StatusLine,
+    DueCount,
+    OverdueCount,
+    NoMenuMatch,
+    MarkdownUnsorted,
+    ServingFeed,
+}
+
+/// English, German and French text for `msg`, in the order of [`Lang::ALL`].
+fn texts(msg: Msg) -> [&'static str; 3] {
+    match msg {
+        Msg::ColNo => ["No", "Nr", "N°"],
+        Msg::ColDate => ["Date", "Datum", "Date"],
+        Msg::ColTask => ["Task", "Aufgabe", "Tâche"],
+        Msg::ColTags => ["Tags", "Tags", "Étiquettes"],
+        Msg::CompletedHeading => ["Completed:", "Erledigt:", "Terminées :"],
+        Msg::NothingToDo => ["Nothing to do.", "Nichts zu tun.", "Rien à faire."],
+        Msg::NoMatchingOpen => [
+            "No matching open entries.",
+            "Keine passenden offenen Einträge.",
+            "Aucune entrée ouverte correspondante.",
+        ],
+        Msg::IndexOutOfRange => [
+            "Index out of range: {} (there are {} visible entries)",
+            "Nummer außerhalb des Bereichs: {} (es gibt {} sichtbare Einträge)",
+            "Numéro hors limites : {} (il y a {} entrées visibles)",
+        ],
+        Msg::QueryNeedsCriterion => [
+            "Query requires at least one of --from, --to, --date, --tag, --project or --overdue",
+            "Die Abfrage braucht mindestens eine der Optionen --from, --to, --date, --tag, --project oder --overdue",
+            "La requête nécessite au moins une des options --from, --to, --date, --tag, --project ou --overdue",
+        ],
+        Msg::MalformedLine => [
+            "Provided line is malformed; expected: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2",
+            "Die Zeile ist fehlerhaft; erwartet: YYYY-MM-DD<TAB>Beschreibung<TAB>tag1,tag2",
+            "La ligne est mal formée ; attendu : YYYY-MM-DD<TAB>Description<TAB>tag1,tag2",
+        ],
+        Msg::MalformedReplacement => [
+            "Replacement line is malformed; expected: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2",
+            "Die Ersatzzeile ist fehlerhaft; erwartet: YYYY-MM-DD<TAB>Beschreibung<TAB>tag1,tag2",
+            "La ligne de remplacement est mal formée ; attendu : YYYY-MM-DD<TAB>Description<TAB>tag1,tag2",
+        ],
+        Msg::InsertedEntry => [
+            "Inserted normalized entry into {}",
+            "Normalisierten Eintrag in {} eingefügt",
+            "Entrée normalisée insérée dans {}",
+        ],
+        Msg::AppendedEntry => [
+            "Appended normalized entry to {}",
+            "Normalisierten Eintrag an {} angehängt",
+            "Entrée normalisée ajoutée à {}",
+        ],
+        Msg::ReplacedEntry => [
+            "Replaced entry {} in {}",
+            "Eintrag {} in {} ersetzt",
+            "Entrée {} remplacée dans {}",
+        ],
+        Msg::CompletedEntry => [
+            "Marked entry {} as complete in {}",
+            "Eintrag {} in {} als erledigt markiert",
+            "Entrée {} marquée comme terminée dans {}",
+        ],
+        Msg::ExportedTo => ["Exported to {}", "Exportiert nach {}", "Exporté vers {}"],
+        Msg::SortedFile => ["Sorted {} by date", "{} nach Datum sortiert", "{} trié par date"],
+        Msg::IndexedEntries => [
+            "Indexed {} entries in {}",
+            "{} Einträge in {} indiziert",
+            "{} entrées indexées dans {}",
+        ],
+        Msg::RemovedIndex => ["Removed index {}", "Index {} entfernt", "Index {} supprimé"],
+        Msg::NoIndex => ["No index for {}", "Kein Index für {}", "Aucun index pour {}"],
+        Msg::NothingToReview => ["Nothing to review.", "Nichts zu prüfen.", "Rien à passer en revue."],
+        Msg::EntriesToReview => [
+            "Entries to review: {}",
+            "Zu prüfende Einträge: {}",
+            "Entrées à passer en revue : {}",
+        ],
+        Msg::Overdue => ["overdue", "überfällig", "en retard"],
+        Msg::Undated => ["no date", "ohne Datum", "sans date"],
+        Msg::Untouched => [
+            "untouched for {} days",
+            "seit {} Tagen unverändert",
+            "inchangée depuis {} jours",
+        ],
+        Msg::TagsLabel => ["tags", "Tags", "étiquettes"],
+        Msg::ReviewActions => [
+            "[r]eschedule, [c]omplete, [d]elete, [k]eep, [q]uit (default keep): ",
+            "r = verschieben, c = erledigt, d = löschen, k = behalten, q = beenden (Standard: behalten): ",
+            "r = replanifier, c = terminer, d = supprimer, k = garder, q = quitter (par défaut : garder) : ",
+        ],
+        Msg::ReviewNewDate => [
+            "New date ({} or e.g. tomorrow, +3d; empty to go back): ",
+            "Neues Datum ({} oder z. B. tomorrow, +3d; leer für zurück): ",
+            "Nouvelle date ({} ou p. ex. tomorrow, +3d ; vide pour revenir) : ",
+        ],
+        Msg::InvalidDate => [
+            "Not a valid date: {}",
+            "Kein gültiges Datum: {}",
+            "Date invalide : {}",
+        ],
+        Msg::ReviewAnswerHint => [
+            "Please answer r, c, d, k or q.",
+            "Bitte mit r, c, d, k oder q antworten.",
+            "Répondez par r, c, d, k ou q.",
+        ],
+        Msg::NoChanges => ["No changes made.", "Keine Änderungen.", "Aucune modification."],
+        Msg::ReviewSaved => [
+            "Review saved: {} rescheduled, {} completed, {} deleted (moved to {}).",
+            "Prüfung gespeichert: {} verschoben, {} erledigt, {} gelöscht (verschoben nach {}).",
+            "Revue enregistrée : {} replanifiées, {} terminées, {} supprimées (déplacées vers {}).",
+        ],
+        Msg::NextOccurrence => [
+            "Repeats: added the next occurrence on {}",
+            "Wiederholung: nächster Termin am {} angelegt",
+            "Répétition : prochaine occurrence ajoutée le {}",
+        ],
+        Msg::RolledEntries => [
+            "Moved {} overdue entries to today",
+            "{} überfällige Einträge auf heute verschoben",
+            "{} entrées en retard déplacées à aujourd'hui",
+        ],
+        Msg::NoContext => ["No context is active.", "Kein Kontext aktiv.", "Aucun contexte actif."],
+        Msg::ActiveContext => ["Active context: {}", "Aktiver Kontext: {}", "Contexte actif : {}"],
+        Msg::ColProject => ["Project", "Projekt", "Projet"],
+        Msg::ColOpen => ["open", "offen", "ouvertes"],
+        Msg::ColDone => ["done", "erledigt", "terminées"],
+        Msg::NoProject => ["(no project)", "(kein Projekt)", "(sans projet)"],
+        Msg::Backlog => ["Backlog", "Offen", "À faire"],
+        Msg::InProgress => ["In Progress", "In Arbeit", "En cours"],
+        Msg::Done => ["Done", "Erledigt", "Terminé"],
+        Msg::StatusSet => [
+            "Set entry {} to {} in {}",
+            "Status von Eintrag {} auf {} gesetzt ({})",
+            "Entrée {} passée à {} dans {}",
+        ],
+        Msg::TooManyInProgress => [
+            "Warning: {} entries are in progress (max_in_progress is {})",
+            "Warnung: {} Einträge sind in Arbeit (max_in_progress ist {})",
+            "Attention : {} entrées sont en cours (max_in_progress vaut {})",
+        ],
+        Msg::WhereNeedsCriterion => [
+            "--where requires at least one of --from, --to, --date, --tag, --project or --status",
+            "--where braucht mindestens eine der Optionen --from, --to, --date, --tag, --project oder --status",
+            "--where nécessite au moins une des options --from, --to, --date, --tag, --project ou --status",
+        ],
+        Msg::FiltersNeedWhere => [
+            "Filter options edit several entries and need --where",
+            "Filteroptionen bearbeiten mehrere Einträge und brauchen --where",
+            "Les options de filtre modifient plusieurs entrées et nécessitent --where",
+        ],
+        Msg::MalformedSubstitution => [
+            "Not a valid substitution: {} (expected /old/new/)",
+            "Keine gültige Ersetzung: {} (erwartet: /alt/neu/)",
+            "Substitution invalide : {} (attendu : /ancien/nouveau/)",
+        ],
+        Msg::NothingToReplace => [
+            "No matching entry contains \"{}\".",
+            "Kein passender Eintrag enthält „{}“.",
+            "Aucune entrée correspondante ne contient « {} ».",
+        ],
+        Msg::ApplyChanges => [
+            "Apply {} changes? [y/N] ",
+            "{} Änderungen übernehmen? [y/N] ",
+            "Appliquer {} modifications ? [y/N] ",
+        ],
+        Msg::EditedEntries => [
+            "Edited {} entries in {}",
+            "{} Einträge in {} bearbeitet",
+            "{} entrées modifiées dans {}",
+        ],
+        Msg::RetagNeedsCriterion => [
+            "Retag requires at least one of --from, --to, --date, --tag, --project or --status",
+            "Retag braucht mindestens eine der Optionen --from, --to, --date, --tag, --project oder --status",
+            "Retag nécessite au moins une des options --from, --to, --date, --tag, --project ou --status",
+        ],
+        Msg::InvalidTag => [
+            "Not a valid tag: \"{}\" (tags can't be empty or contain commas or tabs)",
+            "Kein gültiger Tag: „{}“ (Tags dürfen nicht leer sein und keine Kommas oder Tabs enthalten)",
+            "Étiquette invalide : « {} » (une étiquette ne peut pas être vide ni contenir de virgule ou de tabulation)",
+        ],
+        Msg::RetaggedEntries => [
+            "Retagged {} entries in {}",
+            "Tags von {} Einträgen in {} geändert",
+            "Étiquettes de {} entrées modifiées dans {}",
+        ],
+        Msg::MoveNeedsSelection => [
+            "Move requires an entry number or at least one of --tag, --project or --status",
+            "Move braucht eine Eintragsnummer oder mindestens eine der Optionen --tag, --project oder --status",
+            "Move nécessite un numéro d'entrée ou au moins une des options --tag, --project ou --status",
+        ],
+        Msg::MoveToSameFile => [
+            "{} is the current list file",
+            "{} ist die aktuelle Listendatei",
+            "{} est le fichier de liste actuel",
+        ],
+        Msg::MovedEntries => [
+            "Moved {} entries from {} to {}",
+            "{} Einträge von {} nach {} verschoben",
+            "{} entrées déplacées de {} vers {}",
+        ],
+        Msg::SplitTargetExists => [
+            "{} already exists; choose another --out-dir",
+            "{} existiert bereits; bitte ein anderes --out-dir wählen",
+            "{} existe déjà ; choisissez un autre --out-dir",
+        ],
+        Msg::SplitFile => [
+            "Wrote {} entries into {} files in {}",
+            "{} Einträge in {} Dateien in {} geschrieben",
+            "{} entrées écrites dans {} fichiers dans {}",
+        ],
+        Msg::ImportedEntries => [
+            "Imported {} entries into {}",
+            "{} Einträge in {} importiert",
+            "{} entrées importées dans {}",
+        ],
+        Msg::ImportMerged => [
+            "Imported: {} added, {} skipped, {} conflicting (kept as they are) in {}",
+            "Importiert: {} hinzugefügt, {} übersprungen, {} im Konflikt (unverändert gelassen) in {}",
+            "Import : {} ajoutées, {} ignorées, {} en conflit (laissées telles quelles) dans {}",
+        ],
+        Msg::DownloadFailed => [
+            "Could not download {}: {}",
+            "{} konnte nicht heruntergeladen werden: {}",
+            "Impossible de télécharger {} : {}",
+        ],
+        Msg::NothingToImport => [
+            "No entries found at {}; it must be an IronList, todo.txt or iCalendar file",
+            "Unter {} wurden keine Einträge gefunden; erwartet wird eine IronList-, todo.txt- oder iCalendar-Datei",
+            "Aucune entrée trouvée à {} ; le fichier doit être au format IronList, todo.txt ou iCalendar",
+        ],
+        Msg::ReadOnly => [
+            "{} is read-only here (--read-only or the read_only setting); nothing was changed",
+            "{} ist hier schreibgeschützt (--read-only oder die Einstellung read_only); nichts wurde geändert",
+            "{} est en lecture seule ici (--read-only ou le réglage read_only) ; rien n'a été modifié",
+        ],
+        Msg::RemoteSidecar => [
+            "`{}` is not available for a remote list: its files would only be kept next to the local working copy and never uploaded",
+            "`{}` ist für eine entfernte Liste nicht verfügbar: ihre Dateien lägen nur neben der lokalen Arbeitskopie und würden nie hochgeladen",
+            "`{}` n'est pas disponible pour une liste distante : ses fichiers resteraient à côté de la copie de travail locale sans jamais être envoyés",
+        ],
+        Msg::ColPerson => ["Person", "Person", "Personne"],
+        Msg::Unattributed => ["(nobody)", "(niemand)", "(personne)"],
+        Msg::NoHistory => [
+            "No changes recorded for {} yet.",
+            "Für {} sind noch keine Änderungen aufgezeichnet.",
+            "Aucune modification enregistrée pour {} pour l'instant.",
+        ],
+        Msg::NoSnapshots => [
+            "No snapshots of {} yet.",
+            "Noch keine Schnappschüsse von {}.",
+            "Aucun instantané de {} pour l'instant.",
+        ],
+        Msg::ColTime => ["Time", "Zeit", "Heure"],
+        Msg::ColEntries => ["Entries", "Einträge", "Entrées"],
+        Msg::NoSuchSnapshot => [
+            "No snapshot {} (see `history show`)",
+            "Kein Schnappschuss {} (siehe `history show`)",
+            "Aucun instantané {} (voir `history show`)",
+        ],
+        Msg::NoDifferences => ["No differences.", "Keine Unterschiede.", "Aucune différence."],
+        Msg::RestoredSnapshot => [
+            "Restored snapshot {} into {}",
+            "Schnappschuss {} in {} wiederhergestellt",
+            "Instantané {} restauré dans {}",
+        ],
+        Msg::SnapshotTaken => [
+            "Saved snapshot {} of {}",
+            "Schnappschuss {} von {} gespeichert",
+            "Instantané {} de {} enregistré",
+        ],
+        Msg::SnapshotUnchanged => [
+            "Nothing changed since the latest snapshot.",
+            "Seit dem letzten Schnappschuss hat sich nichts geändert.",
+            "Rien n'a changé depuis le dernier instantané.",
+        ],
+        Msg::OnlyIn => ["Only in {}:", "Nur in {}:", "Uniquement dans {} :"],
+        Msg::Differing => [
+            "In both, but different (- first file, + second file):",
+            "In beiden, aber verschieden (- erste Datei, + zweite Datei):",
+            "Dans les deux, mais différentes (- premier fichier, + second fichier) :",
+        ],
+        Msg::BackupSaved => [
+            "Backup saved to {}",
+            "Sicherung gespeichert unter {}",
+            "Sauvegarde enregistrée dans {}",
+        ],
+        Msg::InvalidAge => [
+            "Not a valid age: {} (expected e.g. 7d, 2w or 3m)",
+            "Kein gültiges Alter: {} (erwartet z. B. 7d, 2w oder 3m)",
+            "Durée invalide : {} (attendu par ex. 7d, 2w ou 3m)",
+        ],
+        Msg::PurgedTrash => [
+            "Purged {} entries from {}",
+            "{} Einträge aus {} endgültig gelöscht",
+            "{} entrées supprimées définitivement de {}",
+        ],
+        Msg::PrunedEntries => [
+            "Moved {} completed entries to {}",
+            "{} erledigte Einträge nach {} verschoben",
+            "{} entrées terminées déplacées vers {}",
+        ],
+        Msg::ColStatus => ["Status", "Status", "Statut"],
+        Msg::ColRepeats => ["Repeats", "Wiederholung", "Répétition"],
+        Msg::AfterCompletion => [
+            "{} after completion",
+            "{} nach Erledigung",
+            "{} après achèvement",
+        ],
+        Msg::ColLine => ["Line", "Zeile", "Ligne"],
+        Msg::ColRaw => ["Stored as", "Gespeichert als", "Enregistrée comme"],
+        Msg::NoLinks => [
+            "This entry has no links",
+            "Dieser Eintrag enthält keine Links",
+            "Cette entrée ne contient aucun lien",
+        ],
+        Msg::WhichLink => [
+            "Open which link? [1-{}] ",
+            "Welchen Link öffnen? [1-{}] ",
+            "Ouvrir quel lien ? [1-{}] ",
+        ],
+        Msg::NoSuchLink => [
+            "No link {} (the entry has {})",
+            "Kein Link {} (der Eintrag hat {})",
+            "Pas de lien {} (l'entrée en a {})",
+        ],
+        Msg::Annotated => [
+            "Added a note to entry {} in {}",
+            "Notiz zu Eintrag {} in {} hinzugefügt",
+            "Note ajoutée à l'entrée {} dans {}",
+        ],
+        Msg::ColNote => ["Note", "Notiz", "Note"],
+        Msg::NotRepeating => [
+            "Entry {} does not repeat (it has no `after:` tag)",
+            "Eintrag {} wiederholt sich nicht (er hat kein `after:`-Tag)",
+            "L'entrée {} ne se répète pas (elle n'a pas d'étiquette `after:`)",
+        ],
+        Msg::SkippedOccurrence => [
+            "Skipped entry {}; the next occurrence is on {}",
+            "Eintrag {} übersprungen; das nächste Vorkommen ist am {}",
+            "Entrée {} sautée ; la prochaine occurrence est le {}",
+        ],
+        Msg::Rescheduled => [
+            "Moved entry {} to {}",
+            "Eintrag {} auf {} verschoben",
+            "Entrée {} déplacée au {}",
+        ],
+        Msg::NothingToPlan => [
+            "Nothing to move: no day is over capacity and nothing is overdue",
+            "Nichts zu verschieben: kein Tag ist überlastet und nichts ist überfällig",
+            "Rien à déplacer : aucun jour n'est surchargé et rien n'est en retard",
+        ],
+        Msg::Planned => [
+            "Rescheduled {} entries in {}",
+            "{} Einträge in {} neu terminiert",
+            "{} entrées replanifiées dans {}",
+        ],
+        Msg::OverCapacity => [
+            "Warning: {} has {} open entries, more than its capacity of {}",
+            "Warnung: {} hat {} offene Einträge, mehr als die Kapazität von {}",
+            "Attention : le {} compte {} entrées ouvertes, plus que sa capacité de {}",
+        ],
+        Msg::OverCapacityHours => [
+            "Warning: {} has {} hours of estimated work, more than its capacity of {}",
+            "Warnung: {} hat {} Stunden geschätzte Arbeit, mehr als die Kapazität von {}",
+            "Attention : le {} compte {} heures de travail estimé, plus que sa capacité de {}",
+        ],
+        Msg::NoDateHeading => ["No date:", "Ohne Datum:", "Sans date :"],
+        Msg::Activated => [
+            "\"{}\" is active again in {}",
+            "„{}“ ist in {} wieder aktiv",
+            "« {} » est de nouveau active dans {}",
+        ],
+        Msg::Captured => ["Captured in {}", "In {} festgehalten", "Noté dans {}"],
+        Msg::InboxEmpty => ["The inbox is empty.", "Der Eingang ist leer.", "La boîte de réception est vide."],
+        Msg::Filed => [
+            "Filed \"{}\" in {}",
+            "„{}“ in {} eingeordnet",
+            "« {} » classée dans {}",
+        ],
+        Msg::Dropped => ["Dropped \"{}\"", "„{}“ verworfen", "« {} » supprimée"],
+        Msg::NothingDueForReview => [
+            "No entries are due for review.",
+            "Keine Einträge sind zur Durchsicht fällig.",
+            "Aucune entrée n'est à revoir.",
+        ],
+        Msg::StatsOpen => [
+            "Open: {} ({} overdue, {} due today, {} undated)",
+            "Offen: {} ({} überfällig, {} heute fällig, {} ohne Datum)",
+            "Ouvertes : {} ({} en retard, {} pour aujourd'hui, {} sans date)",
+        ],
+        Msg::StatsClosed => [
+            "Someday: {}  Done: {}  Cancelled: {}",
+            "Irgendwann: {}  Erledigt: {}  Abgebrochen: {}",
+            "Un jour : {}  Terminées : {}  Annulées : {}",
+        ],
+        Msg::DonePerDay => ["Completed per day:", "Erledigt pro Tag:", "Terminées par jour :"],
+        Msg::DonePerWeek => ["Completed per week:", "Erledigt pro Woche:", "Terminées par semaine :"],
+        Msg::DonePerMonth => ["Completed per month:", "Erledigt pro Monat:", "Terminées par mois :"],
+        Msg::DoneAverage => [
+            "Completed per day on average: {} over the last 7 days, {} over the last 28",
+            "Im Schnitt pro Tag erledigt: {} in den letzten 7 Tagen, {} in den letzten 28",
+            "Terminées par jour en moyenne : {} sur les 7 derniers jours, {} sur les 28 derniers",
+        ],
+        Msg::ScheduledAverage => [
+            "Due per day on average over the next 7 days: {}",
+            "Im Schnitt pro Tag fällig in den nächsten 7 Tagen: {}",
+            "À faire par jour en moyenne sur les 7 prochains jours : {}",
+        ],
+        Msg::NoCompletionDates => [
+            "No completion dates recorded yet: entries completed from now on get a `done:` tag.",
+            "Noch keine Erledigungsdaten: ab jetzt erledigte Einträge erhalten ein `done:`-Tag.",
+            "Aucune date d'achèvement enregistrée : les entrées terminées désormais reçoivent une étiquette `done:`.",
+        ],
+        Msg::OverdueAging => [
+            "Overdue by 1-7 days: {}, 8-30 days: {}, 31-90 days: {}, more: {}",
+            "Überfällig seit 1-7 Tagen: {}, 8-30 Tagen: {}, 31-90 Tagen: {}, länger: {}",
+            "En retard de 1 à 7 jours : {}, 8 à 30 jours : {}, 31 à 90 jours : {}, plus : {}",
+        ],
+        Msg::NothingStale => [
+            "Every open entry was changed within the last {} days.",
+            "Jeder offene Eintrag wurde in den letzten {} Tagen geändert.",
+            "Chaque entrée ouverte a été modifiée au cours des {} derniers jours.",
+        ],
+        Msg::ColTouched => ["Changed", "Geändert", "Modifiée"],
+        Msg::ColDays => ["Days", "Tage", "Jours"],
+        Msg::NoChangeHistory => [
+            "No changes are recorded yet, so it is unknown when these entries were last changed.",
+            "Noch keine Änderungen aufgezeichnet; wann diese Einträge zuletzt geändert wurden, ist unbekannt.",
+            "Aucune modification enregistrée : la date de dernière modification de ces entrées est inconnue.",
+        ],
+        Msg::StatusLine => ["{} due · {} overdue", "{} fällig · {} überfällig", "{} à faire · {} en retard"],
+        Msg::DueCount => ["{} due", "{} fällig", "{} à faire"],
+        Msg::OverdueCount => ["{} overdue", "{} überfällig", "{} en retard"],
+        Msg::NoMenuMatch => [
+            "No entry matches the selected line `{}`; was the list changed meanwhile?",
+            "Kein Eintrag passt zur gewählten Zeile `{}`; wurde die Liste inzwischen geändert?",
+            "Aucune entrée ne correspond à la ligne choisie `{}` ; la liste a-t-elle changé entre-temps ?",
+        ],
+        Msg::MarkdownUnsorted => [
+            "Markdown files are not sorted; their entries keep their place among the notes.",
+            "Markdown-Dateien werden nicht sortiert; ihre Einträge bleiben zwischen den Notizen an ihrem Platz.",
+            "Les fichiers Markdown ne sont pas triés : leurs entrées restent à leur place parmi les notes.",
+        ],
+        Msg::ServingFeed => [
+            "Serving the calendar feed at {} (subscribe with {}); press Ctrl-C to stop",
+            "Kalender-Feed unter {} verfügbar (abonnieren mit {}); Strg-C beendet",
+            "Flux de calendrier servi sur {} (abonnement : {}) ; Ctrl-C pour arrêter",
+        ],
+    }
+}
+
+/// `msg` in the current language.
+pub fn tr(msg: Msg) -> &'static str {
+    let i = Lang::ALL.iter().position(|&l| l == lang()).unwrap_or(0);
+    texts(msg)[i]
+}
+
+/// `msg` in the current language with each `{}` replaced by the next of `args`.
+pub fn trf(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut parts = tr(msg).split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
@@ -0,0 +1,326 @@
+//! Merging entries from another list into the data file for `import`.
+//!
+//! With `--merge`, an imported entry is the same item as one already in the list when both carry
+//! the same external ID (an `id:<value>` tag, e.g. from the system it came from) or, failing
+//! that, when they have the same date and description. Importing the same file twice then adds
+//! nothing the second time.
+//!
+//! [`compare`] lines up two lists the same way for `diff`, to help merging copies by hand.
+//!
+//! Lists downloaded with `--url` may also be todo.txt or iCalendar files; [`Format::detect`]
+//! tells them apart and [`parse_todo_txt`] and [`parse_ics`] turn them into entries.
+
+use std::borrow::Cow;
+use std::io;
+use std::process::Command;
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+
+use crate::{Entry, FileFormat, Status, dates, entry_to_line, parse_line, set_status};
+
+/// What `--merge` does with one imported entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Not in the list yet; it is added.
+    Added,
+    /// Already in the list, unchanged; it is skipped.
+    Skipped,
+    /// The same item is in the list with a different date, description or tags; the list's
+    /// version is kept.
+    Conflict,
+}
+
+/// Counts of each [`Outcome`] for one import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Report {
+    pub added: usize,
+    pub skipped: usize,
+    pub conflicts: usize,
+}
+
+/// The external ID from an entry's `id:<value>` tag, if any.
+pub fn external_id<'a>(e: &'a Entry) -> Option<&'a str> {
+    e.tags.iter().find_map(|t| t.strip_prefix("id:")).filter(|id| !id.is_empty())
+}
+
+fn same_item(a: &Entry, b: &Entry) -> bool {
+    match (external_id(a), external_id(b)) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.date == b.date && a.desc == b.desc,
+    }
+}
+
+fn same_content(a: &Entry, b: &Entry) -> bool {
+    let covers = |x: &Entry, y: &Entry| x.tags.iter().all(|t| y.tags.iter().any(|u| t.eq_ignore_ascii_case(u)));
+    a.date == b.date && a.desc == b.desc && covers(a, b) && covers(b, a)
+}
+
+/// How `e` relates to the entries of `existing`.
+pub fn classify<'e>(e: &Entry, existing: impl IntoIterator<Item = &'e Entry<'e>>) -> Outcome {
+    match existing.into_iter().find(|x| same_item(e, x)) {
+        None => Outcome::Added,
+        Some(x) if same_content(e, x) => Outcome::Skipped,
+        Some(_) => Outcome::Conflict,
+    }
+}
+
+/// The entries of `incoming` that are not in `existing` yet, with the counts. An entry that
+/// occurs twice in `incoming` is added once.
+pub fn merge<'a>(existing: &[Entry<'_>], incoming: Vec<Entry<'a>>) -> (Vec<Entry<'a>>, Report) {
+    let mut report = Report::default();
+    let mut added: Vec<Entry<'a>> = Vec::new();
+    for e in incoming {
+        match classify(&e, existing.iter().chain(&added)) {
+            Outcome::Added => {
+                report.added += 1;
+                added.push(e);
+            }
+            Outcome::Skipped => report.skipped += 1,
+            Outcome::Conflict => report.conflicts += 1,
+        }
+    }
+    (added, report)
+}
+
+/// How two lists differ.
+#[derive(Debug, Default)]
+pub struct Comparison<'x> {
+    /// Entries of the first list with no counterpart in the second
+    pub only_a: Vec<&'x Entry<'x>>,
+    /// Entries of the second list with no counterpart in the first
+    pub only_b: Vec<&'x Entry<'x>>,
+    /// The same item in both lists with a different date, description or tags
+    pub changed: Vec<(&'x Entry<'x>, &'x Entry<'x>)>,
+}
+
+/// Line up the entries of `a` and `b`. Identical entries are paired first. The rest are paired
+/// as the same item when they match like under `--merge` (same `id:` tag, or same date and
+/// description), and failing that when neither has an `id:` tag and the descriptions are the
+/// same, which catches a rescheduled entry.
+pub fn compare<'x>(a: &'x [Entry<'x>], b: &'x [Entry<'x>]) -> Comparison<'x> {
+    let mut used = vec![false; b.len()];
+    let mut pair = |x: &Entry, rule: &dyn Fn(&Entry, &Entry) -> bool| {
+        let j = (0..b.len()).find(|&j| !used[j] && rule(x, &b[j]))?;
+        used[j] = true;
+        Some(&b[j])
+    };
+    let identical = |x: &Entry, y: &Entry| same_item(x, y) && same_content(x, y);
+    let same_description = |x: &Entry, y: &Entry| external_id(x).is_none() && external_id(y).is_none() && x.desc == y.desc;
+
+    let mut rest: Vec<&Entry> = a.iter().filter(|x| pair(x, &identical).is_none()).collect();
+    let mut out = Comparison::default();
+    for rule in [&same_item as &dyn Fn(&Entry, &Entry) -> bool, &same_description] {
+        rest.retain(|x| match pair(x, rule) {
+            Some(y) => {
+                out.changed.push((x, y));
+                false
+            }
+            None => true,
+        });
+    }
+    out.only_a = rest;
+    out.only_b = b.iter().zip(&used).filter(|(_, u)| !**u).map(|(y, _)| y).collect();
+    out
+}
+
+/// Download the list published at `url` with `curl`, returning its content and the content type
+/// the server gave for it. Only `https://` URLs are accepted, and redirects may not leave HTTPS.
+pub fn fetch(url: &str) -> io::Result<(String, Option<String>)> {
+    if !url.starts_with("https://") {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "only https:// URLs can be imported"));
+    }
+    let out = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--proto", "=https"])
+        // the content type goes on a line of its own after the body
+        .args(["--write-out", "\n%{content_type}", "--"])
+        .arg(url)
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(err.kind(), "`curl` is not installed"),
+            _ => err,
+        })?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        return Err(io::Error::other(stderr.trim().to_string()));
+    }
+    let printed = String::from_utf8_lossy(&out.stdout);
+    let (body, content_type) = printed.rsplit_once('\n').unwrap_or((&printed, ""));
+    Ok((body.to_string(), Some(content_type.trim()).filter(|t| !t.is_empty()).map(str::to_string)))
+}
+
+/// The formats a downloaded list can be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    IronList,
+    TodoTxt,
+    ICalendar,
+}
+
+impl Format {
+    /// Tell the format from the content type, or failing that from the content: iCalendar starts
+    /// with `BEGIN:VCALENDAR`, and a file where no line is an IronList entry is read as todo.txt.
+    pub fn detect(content: &str, content_type: Option<&str>) -> Format {
+        let mime = content_type.and_then(|t| t.split(';').next()).map(|t| t.trim().to_ascii_lowercase());
+        if mime.as_deref() == Some("text/calendar") || content.trim_start().starts_with("BEGIN:VCALENDAR") {
+            Format::ICalendar
+        } else if content.lines().any(|l| parse_line(l).is_some()) {
+            Format::IronList
+        } else {
+            Format::TodoTxt
+        }
+    }
+}
+
+/// `s` made fit to be a tag: no commas or tabs, which separate tags in the data file.
+fn tag_text(s: &str) -> String {
+    s.trim().replace([',', '\t'], " ")
+}
+
+/// An entry built from its parts, its raw line being how it is written to the data file.
+fn owned_entry(date: NaiveDate, desc: String, tags: Vec<String>) -> Entry<'static> {
+    let mut e = Entry {
+        date,
+        desc: Cow::Owned(desc),
+        tags: tags.into_iter().map(Arc::from).collect(),
+        raw_line: Cow::Borrowed(""),
+        line: None,
+    };
+    e.raw_line = Cow::Owned(entry_to_line(&e, FileFormat::Tsv));
+    e
+}
+
+/// The tasks of a todo.txt file. `x` marks a task done (its completion date becomes a `done:`
+/// tag), `(A)` becomes `pri:A` (and `priority` for A), `+project` a `project:` tag, `@context` a
+/// tag, and `due:DATE` the entry's date; other `key:value` words are kept as tags.
+pub fn parse_todo_txt(content: &str) -> Vec<Entry<'static>> {
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        let mut words = line.split_whitespace().peekable();
+        let mut tags = Vec::new();
+        let done = words.next_if_eq(&"x").is_some();
+        if done && let Some(day) = words.peek().and_then(|w| NaiveDate::parse_from_str(w, dates::ISO).ok()) {
+            tags.push(format!("done:{}", dates::store(day)));
+            words.next();
+        }
+        // a priority and a creation date may follow, in either order; the creation date says
+        // nothing the list keeps
+        for _ in 0..2 {
+            if let Some(pri) = words.next_if(|w| w.len() == 3 && w.starts_with('(') && w.ends_with(')')) {
+                let letter = &pri[1..2];
+                if letter == "A" {
+                    tags.push("priority".to_string());
+                }
+                tags.push(format!("pri:{}", letter));
+            }
+            words.next_if(|w| NaiveDate::parse_from_str(w, dates::ISO).is_ok());
+        }
+        let (mut date, mut desc) = (dates::UNDATED, Vec::new());
+        for word in words {
+            if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                tags.push(format!("project:{}", tag_text(project)));
+            } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+                tags.push(tag_text(context));
+            } else if let Some(day) = word.strip_prefix("due:").and_then(|d| NaiveDate::parse_from_str(d, dates::ISO).ok()) {
+                date = day;
+            } else if word.split_once(':').is_some_and(|(k, v)| !k.is_empty() && !v.is_empty() && !v.starts_with('/')) {
+                tags.push(tag_text(word));
+            } else {
+                desc.push(word);
+            }
+        }
+        if desc.is_empty() {
+            continue;
+        }
+        let mut e = owned_entry(date, desc.join(" "), tags);
+        if done {
+            // keeps the done: tag, which set_status only drops when reopening
+            set_status(&mut e, Status::Done);
+            e.raw_line = Cow::Owned(entry_to_line(&e, FileFormat::Tsv));
+        }
+        entries.push(e);
+    }
+    entries
+}
+
+/// An iCalendar text value with its escapes (`\,`, `\;`, `\n`, `\\`) undone. Line breaks
+/// become spaces, as a description is a single line.
+fn ics_unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => out.push(' '),
+                Some(escaped) => out.push(escaped),
+                None => {}
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// An event or to-do of an iCalendar file, as far as it has been read.
+#[derive(Default)]
+struct CalendarItem {
+    due: Option<NaiveDate>,
+    start: Option<NaiveDate>,
+    summary: String,
+    tags: Vec<String>,
+    completed: bool,
+}
+
+/// The events and to-dos of an iCalendar file. The summary is the description, the day of `DUE`
+/// (to-dos) or `DTSTART` the date, `CATEGORIES` the tags, and the `UID` an `id:` tag, so
+/// importing the same calendar again with `--merge` adds nothing. Completed to-dos are done.
+pub fn parse_ics(content: &str) -> Vec<Entry<'static>> {
+    // long lines are folded: a line starting with a space or tab continues the one before
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut entries = Vec::new();
+    let mut item: Option<CalendarItem> = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let name = name.split(';').next().unwrap_or(name).to_ascii_uppercase();
+        let component = ["VEVENT", "VTODO"].contains(&value.trim());
+        // DATE values are YYYYMMDD, DATE-TIME values start with it
+        let day = || value.get(..8).and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok());
+        match (name.as_str(), item.as_mut()) {
+            ("BEGIN", None) if component => item = Some(CalendarItem::default()),
+            ("END", Some(_)) if component => {
+                let it = item.take().expect("matched Some");
+                if it.summary.trim().is_empty() {
+                    continue;
+                }
+                let date = it.due.or(it.start).unwrap_or(dates::UNDATED);
+                let mut e = owned_entry(date, it.summary.split_whitespace().collect::<Vec<_>>().join(" "), it.tags);
+                if it.completed {
+                    set_status(&mut e, Status::Done);
+                    e.raw_line = Cow::Owned(entry_to_line(&e, FileFormat::Tsv));
+                }
+                entries.push(e);
+            }
+            ("DUE", Some(it)) => it.due = day(),
+            ("DTSTART", Some(it)) => it.start = day(),
+            ("SUMMARY", Some(it)) => it.summary = ics_unescape(value),
+            ("UID", Some(it)) => {
+                let uid = ics_unescape(value);
+                // UIDs written by `export --format ics` carry the entry's own id
+                let id = uid.strip_suffix("@iron-list").unwrap_or(&uid);
+                it.tags.push(format!("id:{}", tag_text(id)));
+            }
+            ("CATEGORIES", Some(it)) => {
+                it.tags.extend(value.split(',').map(|c| tag_text(&ics_unescape(c))).filter(|c| !c.is_empty()));
+            }
+            ("STATUS", Some(it)) => it.completed = value.trim().eq_ignore_ascii_case("COMPLETED"),
+            _ => {}
+        }
+    }
+    entries
+}
@@ -0,0 +1,198 @@
+//! Optional sidecar index for large files.
+//!
+//! The index lives next to the data file (`<file>.idx`) and records, for every parsed line,
+//! its byte offset, length and line number together with the entry's date and tags. `query` consults it
+//! when present so only the matching lines are read and parsed. The index stores the data
+//! file's length and modification time; when either changes the index is rebuilt before use.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use chrono::NaiveDate;
+
+use crate::{Entry, FileFormat, Filter, dates, TagInterner, in_date_range, parse_line_with, sidecar_path, tags_match};
+
+const HEADER: &str = "ironlist-index v2";
+
+struct Record {
+    offset: u64,
+    len: usize,
+    line: usize,
+    date: NaiveDate,
+    tags: Vec<Arc<str>>,
+}
+
+/// Path of the sidecar index for the data file at `data`.
+pub fn index_path(data: &Path) -> PathBuf {
+    sidecar_path(data, "idx")
+}
+
+/// Length and modification time (nanoseconds since the epoch) of the data file.
+fn stamp(data: &Path) -> io::Result<(u64, u128)> {
+    let meta = std::fs::metadata(data)?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    Ok((meta.len(), mtime))
+}
+
+/// Re-read the data file and write a fresh sidecar index. Returns the number of indexed entries.
+pub fn rebuild(data: &Path) -> io::Result<usize> {
+    let (stamp, records) = scan(data)?;
+    write(data, stamp, &records)?;
+    Ok(records.len())
+}
+
+/// Read the data file into index records, with the stamp it had when read.
+fn scan(data: &Path) -> io::Result<((u64, u128), Vec<Record>)> {
+    let stamp = stamp(data)?;
+    let mut reader = BufReader::new(File::open(data)?);
+    let mut records = Vec::new();
+    let mut offset = 0u64;
+    let mut line = Vec::new();
+    let mut interner = TagInterner::new();
+    let format = FileFormat::of(data);
+    let mut line_no = 0;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        // invalid UTF-8 is read lossily, as `query` does; the record covers the bytes in the file
+        let len = line.iter().rposition(|&b| b != b'\n' && b != b'\r').map_or(0, |i| i + 1);
+        if let Some(e) = parse_line_with(&String::from_utf8_lossy(&line[..len]), &mut interner, format) {
+            records.push(Record {
+                offset,
+                len,
+                line: line_no,
+                date: e.date,
+                tags: e.tags,
+            });
+        }
+        offset += read as u64;
+        line_no += 1;
+    }
+    Ok((stamp, records))
+}
+
+/// Write `records`, read from the data file when it had `stamp`, as its sidecar index.
+fn write(data: &Path, (len, mtime): (u64, u128), records: &[Record]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(index_path(data))?);
+    writeln!(out, "{}", HEADER)?;
+    writeln!(out, "{}\t{}", len, mtime)?;
+    for r in records {
+        writeln!(out, "{}\t{}\t{}\t{}\t{}", r.offset, r.len, r.line, dates::store(r.date), r.tags.join(","))?;
+    }
+    out.flush()
+}
+
+/// Delete the sidecar index. Returns whether one existed.
+pub fn remove(data: &Path) -> io::Result<bool> {
+    let idx = index_path(data);
+    if !idx.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(idx)?;
+    Ok(true)
+}
+
+/// Load the index if it exists and still matches the data file.
+fn load_fresh(data: &Path) -> io::Result<Option<Vec<Record>>> {
+    let idx = index_path(data);
+    if !idx.exists() {
+        return Ok(None);
+    }
+    let mut lines = BufReader::new(File::open(idx)?).lines();
+    if lines.next().transpose()?.as_deref() != Some(HEADER) {
+        return Ok(None);
+    }
+    let (len, mtime) = stamp(data)?;
+    if lines.next().transpose()? != Some(format!("{}\t{}", len, mtime)) {
+        return Ok(None);
+    }
+    let mut records = Vec::new();
+    let mut interner = TagInterner::new();
+    for line in lines {
+        let line = line?;
+        let mut fields = line.split('\t');
+        let (Some(offset), Some(rec_len), Some(line_no), Some(date)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Ok(None);
+        };
+        let (Ok(offset), Ok(rec_len), Ok(line_no), Some(date)) = (
+            offset.parse(),
+            rec_len.parse(),
+            line_no.parse(),
+            dates::parse_stored(date),
+        ) else {
+            return Ok(None);
+        };
+        let tags = fields
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .filter(|t| !t.is_empty())
+            .map(|t| interner.intern(t))
+            .collect();
+        records.push(Record {
+            offset,
+            len: rec_len,
+            line: line_no,
+            date,
+            tags,
+        });
+    }
+    Ok(Some(records))
+}
+
+/// Answer a query from the sidecar index, reading only the matching lines.
+///
+/// Returns `Ok(None)` when no index exists for `data`, in which case the caller should fall
+/// back to a full read. A stale index is rebuilt first; with `read_only` the rebuilt index is
+/// only used for this query and not written.
+pub fn query(data: &Path, filter: &Filter, read_only: bool) -> io::Result<Option<Vec<Entry<'static>>>> {
+    if !index_path(data).exists() {
+        return Ok(None);
+    }
+    let records = match load_fresh(data)? {
+        Some(r) => r,
+        None => {
+            let (stamp, records) = scan(data)?;
+            if !read_only {
+                write(data, stamp, &records)?;
+            }
+            records
+        }
+    };
+
+    let mut f = File::open(data)?;
+    let mut entries = Vec::new();
+    let mut buf = Vec::new();
+    let mut interner = TagInterner::new();
+    let format = FileFormat::of(data);
+    // narrow down on the indexed fields, then check the parsed entry against the full filter
+    for r in records
+        .iter()
+        .filter(|r| in_date_range(r.date, filter.from, filter.to) && tags_match(&r.tags, &filter.tags, filter.any))
+    {
+        f.seek(SeekFrom::Start(r.offset))?;
+        buf.resize(r.len, 0);
+        f.read_exact(&mut buf)?;
+        if let Some(e) = parse_line_with(&String::from_utf8_lossy(&buf), &mut interner, format)
+            && filter.matches(&e)
+        {
+            entries.push(Entry {
+                line: Some(r.line),
+                ..e.into_owned()
+            });
+        }
+    }
+    Ok(Some(entries))
+}
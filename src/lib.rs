@@ -0,0 +1,1227 @@
+//! Core entry parsing, storage and filtering for IronList.
+//!
+//! The CLI in `main.rs` is a thin layer over these functions; they are kept in a library
+//! target so other front-ends (e.g. language bindings) can load, save and query a list
+//! without re-implementing the file format.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::NaiveDate;
+
+pub mod audit;
+pub mod clock;
+pub mod config;
+pub mod dates;
+pub mod export;
+pub mod i18n;
+pub mod import;
+pub mod index;
+pub mod markdown;
+pub mod remote;
+pub mod stats;
+
+/// A parsed entry. On read paths the description and raw line borrow from the buffer the file
+/// was read into; `into_owned` detaches an entry when it has to outlive that buffer.
+#[derive(Debug, Clone)]
+pub struct Entry<'a> {
+    pub date: NaiveDate,
+    pub desc: Cow<'a, str>,
+    pub tags: Vec<Arc<str>>,
+    pub raw_line: Cow<'a, str>,
+    /// 0-based line number in the file the entry was read from (`None` for entries that were
+    /// not read from a file, e.g. a line given on the command line).
+    pub line: Option<usize>,
+}
+
+impl Entry<'_> {
+    pub fn into_owned(self) -> Entry<'static> {
+        Entry {
+            date: self.date,
+            desc: Cow::Owned(self.desc.into_owned()),
+            tags: self.tags,
+            raw_line: Cow::Owned(self.raw_line.into_owned()),
+            line: self.line,
+        }
+    }
+}
+
+/// Where an entry stands. Stored as a `status:<name>` tag; files written before statuses existed
+/// keep working because the `complete` tag means done, and `in-progress`/`waiting` tags are read
+/// as those statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Todo,
+    InProgress,
+    Waiting,
+    Someday,
+    Done,
+    Cancelled,
+}
+
+impl Status {
+    pub const ALL: &[Status] = &[
+        Status::Todo,
+        Status::InProgress,
+        Status::Waiting,
+        Status::Someday,
+        Status::Done,
+        Status::Cancelled,
+    ];
+
+    /// The name used in `status:<name>` tags and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in-progress",
+            Status::Waiting => "waiting",
+            Status::Someday => "someday",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Status> {
+        Status::ALL.iter().copied().find(|st| st.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    /// Whether entries with this status are finished with (done or cancelled).
+    pub fn is_closed(self) -> bool {
+        matches!(self, Status::Done | Status::Cancelled)
+    }
+}
+
+/// The status of `e`: its `status:` tag if it has one, otherwise mapped from the older tags.
+pub fn status(e: &Entry) -> Status {
+    if let Some(st) = e.tags.iter().find_map(|t| t.strip_prefix("status:")).and_then(Status::parse) {
+        return st;
+    }
+    let has = |tag: &str| e.tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+    if has("complete") {
+        Status::Done
+    } else if has("in-progress") {
+        Status::InProgress
+    } else if has("waiting") {
+        Status::Waiting
+    } else {
+        Status::Todo
+    }
+}
+
+/// Give `e` the status `st`, replacing whatever status tags it had. Done is written as the
+/// `complete` tag so older versions still see the entry as finished; todo needs no tag. An entry
+/// that is no longer done loses its `done:` and `done-by:` tags.
+pub fn set_status(e: &mut Entry, st: Status) {
+    e.tags.retain(|t| {
+        !(t.starts_with("status:")
+            || t.eq_ignore_ascii_case("complete")
+            || t.eq_ignore_ascii_case("in-progress")
+            || t.eq_ignore_ascii_case("waiting")
+            || (st != Status::Done && (t.starts_with("done:") || t.starts_with("done-by:"))))
+    });
+    match st {
+        Status::Todo => {}
+        Status::Done => e.tags.push("complete".into()),
+        _ => e.tags.push(Arc::from(format!("status:{}", st.name()))),
+    }
+}
+
+/// Whether an entry can't be worked on yet: it is waiting (status or `waiting` tag) or tagged
+/// `blocked`. Blocked entries are skipped by `next`.
+pub fn is_blocked(e: &Entry) -> bool {
+    status(e) == Status::Waiting || e.tags.iter().any(|t| t.eq_ignore_ascii_case("blocked"))
+}
+
+/// Whether an entry is finished with: done (the `complete` tag) or cancelled. Such entries are
+/// hidden from listings unless `--show-all` is given.
+pub fn is_complete(e: &Entry) -> bool {
+    status(e).is_closed()
+}
+
+/// Whether an entry is open and not parked as someday/maybe: what `list`, `next`, `review`,
+/// `roll`, `plan` and the daily sheet work with.
+pub fn is_active(e: &Entry) -> bool {
+    !matches!(status(e), Status::Done | Status::Cancelled | Status::Someday)
+}
+
+/// Whether an entry is flagged as important with an `urgent` or `priority` tag.
+pub fn is_priority(e: &Entry) -> bool {
+    e.tags
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case("urgent") || t.eq_ignore_ascii_case("priority"))
+}
+
+/// The project an entry belongs to, from its `project:<name>` tag. Projects are kept apart from
+/// ordinary tags: `--tag` filters never match them and reports group by them separately.
+pub fn project<'a>(e: &'a Entry) -> Option<&'a str> {
+    e.tags.iter().find_map(|t| t.strip_prefix("project:")).filter(|p| !p.is_empty())
+}
+
+/// When an entry should next be looked at again, from its `review:YYYY-MM-DD` tag.
+pub fn review_date(e: &Entry) -> Option<NaiveDate> {
+    e.tags
+        .iter()
+        .find_map(|t| t.strip_prefix("review:"))
+        .and_then(|d| NaiveDate::parse_from_str(d, dates::ISO).ok())
+}
+
+/// Who added an entry, from its `by:<name>` tag.
+pub fn added_by<'a>(e: &'a Entry) -> Option<&'a str> {
+    e.tags.iter().find_map(|t| t.strip_prefix("by:")).filter(|n| !n.is_empty())
+}
+
+/// When an entry was completed, from the `done:YYYY-MM-DD` tag completing it adds.
+pub fn completed_on(e: &Entry) -> Option<NaiveDate> {
+    e.tags
+        .iter()
+        .find_map(|t| t.strip_prefix("done:"))
+        .and_then(|d| NaiveDate::parse_from_str(d, dates::ISO).ok())
+}
+
+/// How old a finished entry is measured from: the day it was completed (see [`completed_on`]), or
+/// its date for entries completed before `done:` tags were recorded.
+pub fn finished_on(e: &Entry) -> NaiveDate {
+    completed_on(e).unwrap_or(e.date)
+}
+
+/// Who completed an entry, from its `done-by:<name>` tag.
+pub fn done_by<'a>(e: &'a Entry) -> Option<&'a str> {
+    e.tags.iter().find_map(|t| t.strip_prefix("done-by:")).filter(|n| !n.is_empty())
+}
+
+/// The web and mail links in an entry's description and tags, in order and without duplicates.
+pub fn links<'a>(e: &'a Entry) -> Vec<&'a str> {
+    let mut found: Vec<&str> = Vec::new();
+    let words = e.desc.split_whitespace().chain(e.tags.iter().map(|t| &**t));
+    for word in words {
+        let Some(start) = ["https://", "http://", "mailto:"].iter().filter_map(|p| word.find(p)).min() else {
+            continue;
+        };
+        let link = word[start..].trim_end_matches(['.', ',', ';', ':', ')', ']', '>', '"', '\'']);
+        if !found.contains(&link) {
+            found.push(link);
+        }
+    }
+    found
+}
+
+/// A `note:<time> <text>` tag recording an annotation made at `time`. Whitespace in `text` is
+/// collapsed and `%` and `,` are escaped so the note stays a single tag.
+pub fn annotation_tag(time: &str, text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("note:{} {}", time, text.replace('%', "%25").replace(',', "%2C"))
+}
+
+/// The annotations of an entry (see [`annotation_tag`]) as `(time, text)`, oldest first.
+pub fn annotations<'a>(e: &'a Entry) -> Vec<(&'a str, String)> {
+    e.tags
+        .iter()
+        .filter_map(|t| t.strip_prefix("note:"))
+        .map(|note| {
+            let (time, text) = note.split_once(' ').unwrap_or((note, ""));
+            (time, text.replace("%2C", ",").replace("%25", "%"))
+        })
+        .collect()
+}
+
+/// The estimated effort of an entry in minutes, from an `est:` tag such as `est:30m`, `est:2h` or
+/// `est:1h30m`.
+pub fn estimate_minutes(e: &Entry) -> Option<u32> {
+    let est = e.tags.iter().find_map(|t| t.strip_prefix("est:"))?;
+    let (hours, rest) = match est.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().ok()?, rest),
+        None => (0, est),
+    };
+    let minutes = if rest.is_empty() { 0 } else { rest.strip_suffix('m')?.parse::<u32>().ok()? };
+    Some(hours * 60 + minutes)
+}
+
+/// Give `e` the tag `key:value`, replacing any earlier `key:` tag.
+pub fn set_tag_value(e: &mut Entry, key: &str, value: &str) {
+    let prefix = format!("{}:", key);
+    e.tags.retain(|t| !t.starts_with(&prefix));
+    e.tags.push(Arc::from(format!("{}{}", prefix, value)));
+}
+
+/// The first ordinary tag of an entry, cut at the first `/` (`work/meetings` gives `work`).
+/// `key:value` metadata and status tags are skipped.
+pub fn top_level_tag<'a>(e: &'a Entry) -> Option<&'a str> {
+    e.tags
+        .iter()
+        .map(|t| t.split('/').next().unwrap_or(t).trim())
+        .find(|t| {
+            !t.is_empty()
+                && !t.contains(':')
+                && !["complete", "in-progress", "waiting"].iter().any(|s| t.eq_ignore_ascii_case(s))
+        })
+}
+
+/// For an entry that repeats relative to its completion (an `after:<n>d`, `after:<n>w` or
+/// `after:<n>m` tag), the next occurrence when it was completed on `done`: the same entry, open
+/// again and dated that interval after `done`.
+pub fn next_after_completion(e: &Entry, done: NaiveDate) -> Option<Entry<'static>> {
+    let interval = e.tags.iter().find_map(|t| t.strip_prefix("after:"))?;
+    if !interval.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let date = dates::parse_keyword(&format!("+{}", interval), done)?;
+    let mut next = Entry {
+        date,
+        desc: Cow::Owned(e.desc.to_string()),
+        tags: e.tags.clone(),
+        raw_line: Cow::Borrowed(""),
+        line: None,
+    };
+    set_status(&mut next, Status::Todo);
+    next.raw_line = Cow::Owned(entry_to_line(&next, FileFormat::Tsv));
+    Some(next)
+}
+
+/// The single open, unblocked entry to work on next: the earliest due one, preferring entries tagged
+/// `urgent`/`priority` among those due on the same day, then file order.
+pub fn next_entry<'a, 'e: 'a>(entries: impl IntoIterator<Item = &'a Entry<'e>>) -> Option<&'a Entry<'e>> {
+    entries
+        .into_iter()
+        .filter(|e| is_active(e) && !is_blocked(e))
+        .min_by_key(|e| (e.date, !is_priority(e), e.line))
+}
+
+/// Sort key for listings: in-progress entries pinned to the top, then by date ascending, undated
+/// entries last. Sorts are stable, so file order is kept within a date.
+pub fn list_order(e: &Entry) -> (bool, bool, NaiveDate) {
+    (e.date == dates::UNDATED, status(e) != Status::InProgress, e.date)
+}
+
+/// Return indices (into the original entries slice) for the entries that should be visible
+/// given the `show_all` flag.
+pub fn visible_indices(entries: &[Entry], show_all: bool) -> Vec<usize> {
+    if show_all {
+        (0..entries.len()).collect()
+    } else {
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| is_active(e))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Hands out one shared `Arc<str>` per distinct tag, so a file where thousands of entries are
+/// tagged `work` holds a single `"work"` allocation.
+#[derive(Debug, Default)]
+pub struct TagInterner {
+    tags: HashSet<Arc<str>>,
+}
+
+impl TagInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, tag: &str) -> Arc<str> {
+        if let Some(existing) = self.tags.get(tag) {
+            return Arc::clone(existing);
+        }
+        let tag: Arc<str> = Arc::from(tag);
+        self.tags.insert(Arc::clone(&tag));
+        tag
+    }
+}
+
+/// How a file stores its entries, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// One tab-separated `DATE<TAB>desc<TAB>tags` line per entry
+    Tsv,
+    /// Checklist items among other Markdown, see [`markdown`]
+    Markdown,
+}
+
+impl FileFormat {
+    /// The format of the file at `path`: Markdown for `.md` and `.markdown` files, tab-separated
+    /// otherwise (including the trash, inbox and archive files next to a Markdown list).
+    pub fn of(path: &Path) -> FileFormat {
+        if markdown::is_markdown(path) { FileFormat::Markdown } else { FileFormat::Tsv }
+    }
+}
+
+/// Parse a single tab-separated line. Tags are freshly allocated; readers that parse many lines
+/// use `parse_line_with` to share tag strings.
+pub fn parse_line(line: &str) -> Option<Entry<'_>> {
+    parse_line_with(line, &mut TagInterner::new(), FileFormat::Tsv)
+}
+
+/// Parse a single line of a file in `format`, borrowing the description and raw line from `line`.
+pub fn parse_line_with<'a>(line: &'a str, interner: &mut TagInterner, format: FileFormat) -> Option<Entry<'a>> {
+    match format {
+        FileFormat::Tsv => parse_fields(line, interner, dates::parse_stored),
+        FileFormat::Markdown => markdown::parse(line, interner),
+    }
+}
+
+/// Parse a line typed by the user (`add`, `edit`). Like `parse_line`, but the date may also be
+/// written in the configured display format.
+pub fn parse_input_line(line: &str) -> Option<Entry<'_>> {
+    parse_fields(line, &mut TagInterner::new(), dates::parse_input)
+}
+
+fn parse_fields<'a>(
+    line: &'a str,
+    interner: &mut TagInterner,
+    parse_date: impl Fn(&str) -> Option<NaiveDate>,
+) -> Option<Entry<'a>> {
+    // Expected format: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2
+    // Also accept runs of 4+ spaces as a separator because many shells don't accept literal tabs.
+    // The date may be `-` or left empty for an entry without one.
+    let mut parts: Vec<&str> = split_on_tab_or_spaces(line);
+    if line.starts_with('\t') {
+        parts.insert(0, "-");
+    }
+    if parts.len() < 2 {
+        return None;
+    }
+    let date = match parts[0].trim() {
+        "-" => dates::UNDATED,
+        s => parse_date(s)?,
+    };
+    let desc = Cow::Borrowed(parts[1].trim());
+    let tags = if parts.len() >= 3 {
+        parts[2]
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| interner.intern(s))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Some(Entry {
+        date,
+        desc,
+        tags,
+        raw_line: Cow::Borrowed(line),
+        line: None,
+    })
+}
+
+/// Parse the `line_no`th (0-based) line of a file, remembering where it came from.
+fn parse_file_line<'a>(line: &'a str, line_no: usize, interner: &mut TagInterner, format: FileFormat) -> Option<Entry<'a>> {
+    parse_line_with(line, interner, format).map(|e| Entry {
+        line: Some(line_no),
+        ..e
+    })
+}
+
+/// Split a line into fields using either tab characters or runs of 4+ spaces as separators.
+fn split_on_tab_or_spaces(s: &str) -> Vec<&str> {
+    let bytes = s.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0usize;
+    let mut i = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\t' => {
+                // separator at i
+                parts.push(s[start..i].trim());
+                i += 1;
+                start = i;
+            }
+            b' ' => {
+                // count run of spaces
+                let mut j = i;
+                while j < bytes.len() && bytes[j] == b' ' {
+                    j += 1;
+                }
+                if j - i >= 4 {
+                    // treat as separator
+                    parts.push(s[start..i].trim());
+                    // skip all spaces
+                    i = j;
+                    start = i;
+                    continue;
+                } else {
+                    // not a separator, continue
+                    i = j;
+                    continue;
+                }
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+    // push remainder
+    if start <= s.len() {
+        parts.push(s[start..].trim());
+    }
+    // filter out empty parts that may occur
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Lines that aren't entries but aren't mistakes either: `#include` directives, and in a
+/// Markdown file everything but checklist items.
+fn quietly_skipped(line: &str, format: FileFormat) -> bool {
+    format == FileFormat::Markdown || include_target(line).is_some()
+}
+
+/// Inputs at least this large are parsed on several threads by `parse_entries`.
+const PARALLEL_PARSE_THRESHOLD: usize = 1 << 20;
+
+/// Read and parse the file at `path` into entries that own their data.
+pub fn read_entries(path: &Path) -> io::Result<Vec<Entry<'static>>> {
+    Ok(entries_iter(path)?.collect())
+}
+
+/// Content hash of each file as it was last read with [`read_file`] or written here. Writes
+/// compare it with the file's current content, so edits made in the meantime by another process
+/// or a sync tool are reported instead of overwritten.
+static SEEN: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+
+pub(crate) fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn seen() -> std::sync::MutexGuard<'static, HashMap<PathBuf, u64>> {
+    SEEN.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn remember(path: &Path, bytes: &[u8]) {
+    seen().insert(path.to_path_buf(), content_hash(bytes));
+}
+
+/// Fail if the file at `path`, whose content is now `current`, was read earlier and has changed
+/// since.
+fn check_unchanged(path: &Path, current: &[u8]) -> io::Result<()> {
+    match seen().get(path) {
+        Some(&hash) if hash != content_hash(current) => Err(io::Error::other(format!(
+            "{} changed since it was read (edited by another program?); nothing was written, re-run the command",
+            path.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The content of the file at `path` before rewriting it (empty if it doesn't exist), checked
+/// with [`check_unchanged`].
+fn read_for_write(path: &Path) -> io::Result<Vec<u8>> {
+    let content = match std::fs::read(path) {
+        Ok(c) => c,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    check_unchanged(path, &content)?;
+    Ok(content)
+}
+
+/// Write `content` to the file at `path` and remember it as read.
+fn write_checked(path: &Path, content: Vec<u8>) -> io::Result<()> {
+    std::fs::write(path, &content)?;
+    remember(path, &content);
+    Ok(())
+}
+
+/// Read the whole file at `path` into one buffer for `parse_entries` to borrow from.
+/// Invalid UTF-8 is replaced rather than rejected.
+pub fn read_file(path: &Path) -> io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    remember(path, &bytes);
+    Ok(String::from_utf8(bytes).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
+}
+
+/// Parse every line of `content`, a file in `format`, in file order, with descriptions and raw
+/// lines borrowed from it. Large inputs are parsed on several threads. Malformed lines are
+/// reported and skipped.
+pub fn parse_entries(content: &str, format: FileFormat) -> Vec<Entry<'_>> {
+    if content.len() >= PARALLEL_PARSE_THRESHOLD {
+        return parse_parallel(content, format);
+    }
+    let mut interner = TagInterner::new();
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, l)| {
+            let parsed = parse_file_line(l, i, &mut interner, format);
+            if parsed.is_none() && !quietly_skipped(l, format) {
+                eprintln!("Skipping malformed line {}: {}", i + 1, l);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Parse `content` in contiguous chunks of lines, one chunk per available core, and stitch the
+/// results back together in file order. Malformed lines are reported with their line numbers.
+fn parse_parallel(content: &str, format: FileFormat) -> Vec<Entry<'_>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk_len = lines.len().div_ceil(threads).max(1);
+
+    // each chunk yields either a parsed entry or the (0-based) number of the malformed line
+    let chunks: Vec<Vec<Result<Entry<'_>, usize>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = lines
+            .chunks(chunk_len)
+            .enumerate()
+            .map(|(ci, chunk)| {
+                scope.spawn(move || {
+                    let mut interner = TagInterner::new();
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(i, l)| parse_file_line(l, ci * chunk_len + i, &mut interner, format).ok_or(ci * chunk_len + i))
+                        .collect()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("parser thread panicked"))
+            .collect()
+    });
+
+    // each thread interned on its own; re-intern so tags are shared across chunks as well
+    let mut interner = TagInterner::new();
+    let mut entries = Vec::with_capacity(lines.len());
+    for parsed in chunks.into_iter().flatten() {
+        match parsed {
+            Ok(mut e) => {
+                for t in &mut e.tags {
+                    *t = interner.intern(t);
+                }
+                entries.push(e);
+            }
+            Err(i) if !quietly_skipped(lines[i], format) => eprintln!("Skipping malformed line {}: {}", i + 1, lines[i]),
+            Err(_) => {}
+        }
+    }
+    entries
+}
+
+/// Stream entries from `path` one line at a time, in file order. Malformed lines are reported
+/// on stderr and skipped. Each line is read into its own buffer, so the entries own their data.
+pub fn entries_iter(path: &Path) -> io::Result<impl Iterator<Item = Entry<'static>>> {
+    let reader = BufReader::new(File::open(path)?);
+    let format = FileFormat::of(path);
+    let mut interner = TagInterner::new();
+    Ok(reader.lines().enumerate().filter_map(move |(i, line)| match line {
+        Ok(l) => {
+            let parsed = parse_file_line(&l, i, &mut interner, format).map(|e| (e.date, e.desc.into_owned(), e.tags));
+            match parsed {
+                // the raw line is the buffer we already own; move it in instead of copying it
+                Some((date, desc, tags)) => Some(Entry {
+                    date,
+                    desc: Cow::Owned(desc),
+                    tags,
+                    raw_line: Cow::Owned(l),
+                    line: Some(i),
+                }),
+                None => {
+                    if !quietly_skipped(&l, format) {
+                        eprintln!("Skipping malformed line {}: {}", i + 1, l);
+                    }
+                    None
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("Error reading line {}: {}", i + 1, err);
+            None
+        }
+    }))
+}
+
+/// Keeps the `n` entries seen so far that come first in [`list_order`] (ties broken by insertion
+/// order), so a limited listing only holds `n` entries in memory instead of the whole file.
+pub struct EarliestN<'a> {
+    n: usize,
+    seq: usize,
+    heap: BinaryHeap<Ranked<'a>>,
+}
+
+struct Ranked<'a> {
+    seq: usize,
+    entry: Entry<'a>,
+}
+
+impl Ranked<'_> {
+    fn key(&self) -> ((bool, bool, NaiveDate), usize) {
+        (list_order(&self.entry), self.seq)
+    }
+}
+
+impl PartialEq for Ranked<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Ranked<'_> {}
+
+impl PartialOrd for Ranked<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl<'a> EarliestN<'a> {
+    pub fn new(n: usize) -> Self {
+        EarliestN {
+            n,
+            seq: 0,
+            heap: BinaryHeap::with_capacity(n + 1),
+        }
+    }
+
+    pub fn push(&mut self, entry: Entry<'a>) {
+        let ranked = Ranked { seq: self.seq, entry };
+        self.seq += 1;
+        if self.heap.len() < self.n {
+            self.heap.push(ranked);
+        } else if let Some(mut latest) = self.heap.peek_mut()
+            && ranked < *latest
+        {
+            // the heap is a max-heap, so its top is the last entry currently kept
+            *latest = ranked;
+        }
+    }
+
+    /// Number of entries currently kept (at most `n`).
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The kept entries in list order (file order within a date).
+    pub fn into_sorted(self) -> Vec<Entry<'a>> {
+        self.heap.into_sorted_vec().into_iter().map(|r| r.entry).collect()
+    }
+}
+
+pub fn append_entry(path: &Path, line: &str) -> io::Result<()> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+
+    // appending can't clobber anything; keep the remembered content current if it was
+    let before = seen().contains_key(path).then(|| std::fs::read(path).unwrap_or_default());
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    f.write_all(line.as_bytes())?;
+    f.write_all(b"\n")?;
+    if let Some(mut content) = before.filter(|b| check_unchanged(path, b).is_ok()) {
+        content.extend_from_slice(line.as_bytes());
+        content.push(b'\n');
+        remember(path, &content);
+    }
+    Ok(())
+}
+
+/// Replace the file at `path` with `content` as is. Fails without writing if the file changed
+/// since it was last read.
+pub fn replace_file(path: &Path, content: &str) -> io::Result<()> {
+    read_for_write(path)?;
+    write_checked(path, content.as_bytes().to_vec())
+}
+
+/// Replace the file at `path` with `entries`, normalized. Fails without writing if the file
+/// changed since it was last read.
+pub fn write_entries_to_file(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+
+    read_for_write(path)?;
+    let format = FileFormat::of(path);
+    let mut content = Vec::new();
+    for e in entries {
+        content.extend_from_slice(entry_to_line(e, format).as_bytes());
+        content.push(b'\n');
+    }
+    write_checked(path, content)
+}
+
+/// Replace whole lines of the file at `path`, addressed by 0-based line number, with new text.
+/// Every other byte of the file, including each line's original line ending, is left untouched.
+pub fn replace_lines(path: &Path, replacements: &[(usize, String)]) -> io::Result<()> {
+    let changes: Vec<(usize, Option<String>)> = replacements.iter().map(|(n, t)| (*n, Some(t.clone()))).collect();
+    change_lines(path, &changes)
+}
+
+/// Apply several line changes to the file at `path` in one rewrite: `Some(text)` replaces the
+/// line, `None` removes it. Line numbers refer to the file as it is before the change. Every
+/// other byte, including each replaced line's original line ending, is left untouched.
+pub fn change_lines(path: &Path, changes: &[(usize, Option<String>)]) -> io::Result<()> {
+    let content = std::fs::read(path)?;
+    check_unchanged(path, &content)?;
+    // the first change given for a line wins
+    let mut by_line: HashMap<usize, Option<&String>> = HashMap::with_capacity(changes.len());
+    for (n, text) in changes {
+        by_line.entry(*n).or_insert(text.as_ref());
+    }
+    let mut out = Vec::with_capacity(content.len());
+    let mut line_count = 0usize;
+    for (line_no, segment) in content.split_inclusive(|&b| b == b'\n').enumerate() {
+        line_count = line_no + 1;
+        match by_line.get(&line_no) {
+            Some(Some(text)) => {
+                let ending = if segment.ends_with(b"\r\n") {
+                    2
+                } else if segment.ends_with(b"\n") {
+                    1
+                } else {
+                    0
+                };
+                out.extend_from_slice(text.as_bytes());
+                out.extend_from_slice(&segment[segment.len() - ending..]);
+            }
+            Some(None) => {}
+            None => out.extend_from_slice(segment),
+        }
+    }
+    if let Some(n) = by_line.keys().filter(|&&n| n >= line_count).min() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("line {} is past the end of {}", n + 1, path.display()),
+        ));
+    }
+    write_checked(path, out)
+}
+
+/// Path of a file kept next to the data file, e.g. `sidecar_path("list.txt", "idx")` is
+/// `list.txt.idx`.
+pub fn sidecar_path(data: &Path, extension: &str) -> PathBuf {
+    let mut name = data.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// The file named by an `#include other-list.txt` line, as written.
+fn include_target(line: &str) -> Option<&str> {
+    line.trim().strip_prefix("#include ").map(str::trim).filter(|t| !t.is_empty())
+}
+
+/// Every file that `path` pulls in with `#include` lines, directly or through the files it
+/// includes, in the order they are first reached. Include paths are relative to the including
+/// file. A file included twice is only listed once; an include leading back to a file that is
+/// already being read is a cycle and, like a missing file, is reported and skipped.
+pub fn included_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    fn visit(path: &Path, reading: &mut Vec<PathBuf>, seen: &mut HashSet<PathBuf>, out: &mut Vec<PathBuf>) -> io::Result<()> {
+        // read lossily, like the entries themselves, so a stray invalid byte doesn't stop the listing
+        let bytes = std::fs::read(path)?;
+        let content = String::from_utf8_lossy(&bytes);
+        reading.push(std::fs::canonicalize(path)?);
+        for target in content.lines().filter_map(include_target) {
+            let included = path.parent().unwrap_or(Path::new("")).join(target);
+            let Ok(id) = std::fs::canonicalize(&included) else {
+                eprintln!("Skipping #include of missing file {} in {}", included.display(), path.display());
+                continue;
+            };
+            if reading.contains(&id) {
+                eprintln!("Skipping #include {} in {}: it leads back to a file including it", target, path.display());
+            } else if seen.insert(id) {
+                out.push(included.clone());
+                visit(&included, reading, seen, out)?;
+            }
+        }
+        reading.pop();
+        Ok(())
+    }
+    let mut seen = HashSet::from([std::fs::canonicalize(path)?]);
+    let mut out = Vec::new();
+    visit(path, &mut Vec::new(), &mut seen, &mut out)?;
+    Ok(out)
+}
+
+/// Path of the trash file that deleted entries are moved to.
+pub fn trash_path(data: &Path) -> PathBuf {
+    sidecar_path(data, "trash")
+}
+
+/// Path of the inbox file that `in` captures thoughts into.
+pub fn inbox_path(data: &Path) -> PathBuf {
+    sidecar_path(data, "inbox")
+}
+
+/// Path of the archive file that old completed entries are moved to.
+pub fn archive_path(data: &Path) -> PathBuf {
+    sidecar_path(data, "archive")
+}
+
+/// Append `entries` to the trash file next to `data`, each tagged `deleted:<today>` so they can
+/// be found (and restored by hand) later. The entries are not removed from `data`.
+pub fn append_to_trash(data: &Path, entries: &[&Entry<'_>], today: NaiveDate) -> io::Result<()> {
+    let deleted: Arc<str> = Arc::from(format!("deleted:{}", today.format("%Y-%m-%d")));
+    for e in entries {
+        let mut tags = e.tags.clone();
+        tags.push(Arc::clone(&deleted));
+        let trashed = Entry {
+            tags,
+            ..(*e).clone()
+        };
+        let trash = trash_path(data);
+        append_entry(&trash, &entry_to_line(&trashed, FileFormat::of(&trash)))?;
+    }
+    Ok(())
+}
+
+/// Add `entries` to the file at `path`, normalized: at their date positions when `sorted` is set,
+/// appended otherwise. The file is created if missing; its other lines stay as they are.
+pub fn add_entries(path: &Path, entries: &[&Entry<'_>], sorted: bool) -> io::Result<()> {
+    for e in entries {
+        let added = Entry { line: None, ..(*e).clone() };
+        if sorted {
+            write_entry_sorted(path, &added)?;
+        } else {
+            // don't glue the first new entry onto a last line that lacks its newline
+            if let Ok(content) = std::fs::read(path)
+                && !content.is_empty()
+                && !content.ends_with(b"\n")
+            {
+                append_entry(path, "")?;
+            }
+            append_entry(path, &entry_to_line(&added, FileFormat::of(path)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Move `entries` (read from `from`) to the file `to`: they are added to `to` first (see
+/// [`add_entries`]) and then removed from `from`. All other lines of both files stay as they are.
+pub fn move_entries(from: &Path, to: &Path, entries: &[&Entry<'_>], sorted: bool) -> io::Result<()> {
+    let lines = entries
+        .iter()
+        .map(|e| {
+            e.line.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "entry was not read from a file"))
+        })
+        .collect::<io::Result<Vec<usize>>>()?;
+    add_entries(to, entries, sorted)?;
+    let removals: Vec<(usize, Option<String>)> = lines.into_iter().map(|n| (n, None)).collect();
+    change_lines(from, &removals)
+}
+
+/// Remove entries from the trash file next to `data` that were deleted before `cutoff` (by their
+/// `deleted:` tag), or all of them when `cutoff` is `None`. Returns how many were removed.
+pub fn purge_trash(data: &Path, cutoff: Option<NaiveDate>) -> io::Result<usize> {
+    let trash = trash_path(data);
+    if !trash.exists() {
+        return Ok(0);
+    }
+    let content = read_file(&trash)?;
+    let removals: Vec<(usize, Option<String>)> = parse_entries(&content, FileFormat::of(&trash))
+        .iter()
+        .filter(|e| {
+            let deleted = e
+                .tags
+                .iter()
+                .find_map(|t| t.strip_prefix("deleted:"))
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            cutoff.is_none_or(|c| deleted.is_some_and(|d| d < c))
+        })
+        .filter_map(|e| e.line)
+        .map(|n| (n, None))
+        .collect();
+    if !removals.is_empty() {
+        change_lines(&trash, &removals)?;
+    }
+    Ok(removals.len())
+}
+
+/// Write `e` back over the line it was read from, normalized, leaving the rest of the file as is.
+pub fn rewrite_entry(path: &Path, e: &Entry) -> io::Result<()> {
+    let line = e.line.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "entry was not read from a file")
+    })?;
+    replace_lines(path, &[(line, entry_to_line(e, FileFormat::of(path)))])
+}
+
+/// Date of the entry on a raw file line, if the line is an entry.
+fn segment_date(segment: &[u8]) -> Option<NaiveDate> {
+    let text = String::from_utf8_lossy(segment);
+    parse_line(text.trim_end_matches(['\n', '\r'])).map(|e| e.date)
+}
+
+/// Concatenate raw file lines, making sure every line but the last ends with a newline (a line
+/// that was last in the file may lack one before it is moved or followed by another line).
+fn join_segments<'a>(segments: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for seg in segments {
+        if !out.is_empty() && !out.ends_with(b"\n") {
+            out.push(b'\n');
+        }
+        out.extend_from_slice(seg);
+    }
+    out
+}
+
+/// Insert `e` at its date position in a file kept sorted by date (after any entries on the same
+/// date). An entry that was read from the file (`line` is set) is removed from its old position
+/// first, so this also moves an entry whose date changed. All other lines are left untouched.
+/// A Markdown file keeps the order its author gave it: there the entry is rewritten in place or
+/// appended instead.
+pub fn write_entry_sorted(path: &Path, e: &Entry) -> io::Result<()> {
+    if FileFormat::of(path) == FileFormat::Markdown {
+        return match e.line {
+            Some(_) => rewrite_entry(path, e),
+            None => append_entry(path, &entry_to_line(e, FileFormat::Markdown)),
+        };
+    }
+    let content = read_for_write(path)?;
+    let mut segments: Vec<&[u8]> = content.split_inclusive(|&b| b == b'\n').collect();
+    if let Some(line) = e.line {
+        if line >= segments.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("line {} is past the end of {}", line + 1, path.display()),
+            ));
+        }
+        segments.remove(line);
+    }
+    let at = segments
+        .iter()
+        .position(|seg| segment_date(seg).is_some_and(|d| d > e.date))
+        .unwrap_or(segments.len());
+    let new_line = format!("{}\n", entry_to_line(e, FileFormat::Tsv));
+    segments.insert(at, new_line.as_bytes());
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    write_checked(path, join_segments(segments))
+}
+
+/// Move every open entry that is overdue (by `clock`, honoring `tz:` tags) to today. With `mark`,
+/// each moved entry also gets a `rolled:<n>` tag counting how often it has been rolled. Returns
+/// the number of entries moved; only their lines are rewritten.
+pub fn roll_overdue(path: &Path, clock: &clock::Clock, mark: bool) -> io::Result<usize> {
+    // read with read_file, so edits made by another program before the rewrite are caught
+    let content = read_file(path)?;
+    let format = FileFormat::of(path);
+    let mut changes = Vec::new();
+    for e in parse_entries(&content, format) {
+        let today = clock.today_for(&e);
+        let Some(line) = e.line else { continue };
+        if !is_active(&e) || e.date >= today {
+            continue;
+        }
+        let mut rolled = Entry { date: today, ..e };
+        if mark {
+            let count = rolled
+                .tags
+                .iter()
+                .find_map(|t| t.strip_prefix("rolled:").and_then(|n| n.parse::<u32>().ok()))
+                .unwrap_or(0);
+            rolled.tags.retain(|t| !t.starts_with("rolled:"));
+            rolled.tags.push(Arc::from(format!("rolled:{}", count + 1)));
+        }
+        changes.push((line, Some(entry_to_line(&rolled, format))));
+    }
+    if !changes.is_empty() {
+        change_lines(path, &changes)?;
+    }
+    Ok(changes.len())
+}
+
+/// Reorder the file at `path` by date (stable, so same-day entries keep their order). Entry
+/// lines are moved as-is rather than re-normalized; lines that are not entries go to the end.
+/// A Markdown file is left alone, as its notes and headings would lose their place.
+pub fn sort_file(path: &Path) -> io::Result<()> {
+    if FileFormat::of(path) == FileFormat::Markdown {
+        return Ok(());
+    }
+    let content = std::fs::read(path)?;
+    check_unchanged(path, &content)?;
+    let (mut dated, other): (Vec<_>, Vec<_>) = content
+        .split_inclusive(|&b| b == b'\n')
+        .map(|seg| (segment_date(seg), seg))
+        .partition(|(date, _)| date.is_some());
+    dated.sort_by_key(|(date, _)| *date);
+    write_checked(path, join_segments(dated.into_iter().chain(other).map(|(_, seg)| seg)))
+}
+
+/// `e` as a line of a file in `format`, normalized.
+pub fn entry_to_line(e: &Entry, format: FileFormat) -> String {
+    if format == FileFormat::Markdown {
+        return markdown::format(e);
+    }
+    let tag_str = if e.tags.is_empty() { String::new() } else { e.tags.join(",") };
+    if tag_str.is_empty() {
+        format!("{}\t{}", dates::store(e.date), e.desc)
+    } else {
+        format!("{}\t{}\t{}", dates::store(e.date), e.desc, tag_str)
+    }
+}
+
+/// Whether `date` falls inside the inclusive `from`..=`to` range (open-ended when `None`). An
+/// undated entry is only inside the range that has neither end.
+pub fn in_date_range(date: NaiveDate, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+    if date == dates::UNDATED {
+        return from.is_none() && to.is_none();
+    }
+    from.is_none_or(|f| date >= f) && to.is_none_or(|t| date <= t)
+}
+
+/// Whether `entry_tags` satisfy the query `tags` (case-insensitive). With `any` a single match is
+/// enough (OR semantics); otherwise every query tag must be present (AND semantics). An empty
+/// query matches everything.
+pub fn tags_match<T: AsRef<str>>(entry_tags: &[T], tags: &[String], any: bool) -> bool {
+    if tags.is_empty() {
+        return true;
+    }
+    let has = |q: &String| entry_tags.iter().any(|et| et.as_ref().eq_ignore_ascii_case(q));
+    if any { tags.iter().any(has) } else { tags.iter().all(has) }
+}
+
+/// A query over entries. Every criterion that is set must hold; unset criteria match everything.
+/// `matches` is evaluated once per entry, so a query is a single pass over borrowed entries.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Inclusive start date
+    pub from: Option<NaiveDate>,
+    /// Inclusive end date
+    pub to: Option<NaiveDate>,
+    /// Tags to look for (case-insensitive)
+    pub tags: Vec<String>,
+    /// Match any of `tags` instead of all of them
+    pub any: bool,
+    /// Project the entry must belong to (case-insensitive)
+    pub project: Option<String>,
+    /// Status the entry must have
+    pub status: Option<Status>,
+    /// Person who added or completed the entry (case-insensitive)
+    pub by: Option<String>,
+    /// Only entries that are still open (see [`is_active`])
+    pub open: bool,
+}
+
+impl Filter {
+    pub fn matches(&self, e: &Entry) -> bool {
+        in_date_range(e.date, self.from, self.to)
+            && tags_match(&e.tags, &self.tags, self.any)
+            && self
+                .project
+                .as_deref()
+                .is_none_or(|want| project(e).is_some_and(|p| p.eq_ignore_ascii_case(want)))
+            && self.status.is_none_or(|st| status(e) == st)
+            && (!self.open || is_active(e))
+            && self.by.as_deref().is_none_or(|want| {
+                [added_by(e), done_by(e)].into_iter().flatten().any(|n| n.eq_ignore_ascii_case(want))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for a test, under the system's temp directory.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("iron-list-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn include_target_reads_the_path() {
+        assert_eq!(include_target("#include projects/garden.txt"), Some("projects/garden.txt"));
+        assert_eq!(include_target("  #include   a.txt  "), Some("a.txt"));
+        assert_eq!(include_target("#include "), None);
+        assert_eq!(include_target("2026-10-16\t#include a.txt"), None);
+    }
+
+    #[test]
+    fn included_files_follows_nested_includes_once() {
+        let dir = scratch_dir("includes-nested");
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        std::fs::write(dir.join("main.txt"), "#include projects/a.txt\n#include b.txt\n#include b.txt\n").unwrap();
+        std::fs::write(dir.join("projects/a.txt"), "#include ../b.txt\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "2026-10-16\tB\n").unwrap();
+        let found = included_files(&dir.join("main.txt")).unwrap();
+        assert_eq!(found, vec![dir.join("projects/a.txt"), dir.join("projects/../b.txt")]);
+    }
+
+    #[test]
+    fn included_files_skips_cycles_and_missing_files() {
+        let dir = scratch_dir("includes-cycle");
+        std::fs::write(dir.join("a.txt"), "#include b.txt\n#include missing.txt\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "#include c.txt\n").unwrap();
+        std::fs::write(dir.join("c.txt"), "#include a.txt\n#include b.txt\n").unwrap();
+        let found = included_files(&dir.join("a.txt")).unwrap();
+        assert_eq!(found, vec![dir.join("b.txt"), dir.join("c.txt")]);
+    }
+
+    #[test]
+    fn change_lines_keeps_every_other_byte() {
+        let path = scratch_dir("change-lines").join("list.txt");
+        std::fs::write(&path, "2026-10-16\tA\r\n# note\n2026-10-17\tB\n2026-10-18\tC").unwrap();
+        change_lines(&path, &[(0, Some("2026-10-16\tA2".to_string())), (2, None), (3, Some("2026-10-18\tC2".to_string()))]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2026-10-16\tA2\r\n# note\n2026-10-18\tC2");
+    }
+
+    #[test]
+    fn change_lines_refuses_lines_past_the_end() {
+        let path = scratch_dir("change-lines-end").join("list.txt");
+        std::fs::write(&path, "2026-10-16\tA\n").unwrap();
+        let err = change_lines(&path, &[(0, None), (1, None)]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2026-10-16\tA\n");
+    }
+
+    #[test]
+    fn replace_lines_refuses_a_file_changed_since_read() {
+        let path = scratch_dir("replace-lines").join("list.txt");
+        std::fs::write(&path, "2026-10-16\tA\n2026-10-17\tB\n").unwrap();
+        read_file(&path).unwrap();
+        replace_lines(&path, &[(1, "2026-10-17\tB2".to_string())]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2026-10-16\tA\n2026-10-17\tB2\n");
+
+        // our own write counts as read; someone else's doesn't
+        std::fs::write(&path, "2026-10-16\tEdited elsewhere\n2026-10-17\tB2\n").unwrap();
+        let err = replace_lines(&path, &[(1, "2026-10-17\tB3".to_string())]).unwrap_err();
+        assert!(err.to_string().contains("changed since it was read"), "{}", err);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2026-10-16\tEdited elsewhere\n2026-10-17\tB2\n");
+    }
+
+    #[test]
+    fn include_lines_are_not_malformed_entries() {
+        let content = "2026-10-16\tA\n#include other.txt\n";
+        let entries = parse_entries(content, FileFormat::Tsv);
+        assert_eq!(entries.len(), 1);
+        assert!(quietly_skipped("#include other.txt", FileFormat::Tsv));
+        assert!(!quietly_skipped("not an entry", FileFormat::Tsv));
+    }
+
+    #[test]
+    fn index_reads_invalid_utf8_lossily() {
+        let path = scratch_dir("index-utf8").join("list.txt");
+        std::fs::write(&path, b"2026-10-16\tCaf\xe9 run\twork\n2026-10-17\tReport\twork\n").unwrap();
+        assert_eq!(index::rebuild(&path).unwrap(), 2);
+        let filter = Filter { tags: vec!["work".to_string()], ..Filter::default() };
+        let found = index::query(&path, &filter, true).unwrap().unwrap();
+        let descs: Vec<&str> = found.iter().map(|e| e.desc.as_ref()).collect();
+        assert_eq!(descs, ["Caf\u{fffd} run", "Report"]);
+    }
+}
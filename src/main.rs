@@ -1,16 +1,43 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::path::PathBuf;
+use std::borrow::{Borrow, Cow};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, OnceLock};
 
-use chrono::NaiveDate;
-use clap::{Parser, Subcommand};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use iron_list::audit;
+use iron_list::clock::Clock;
+use iron_list::config::{self, Config};
+use iron_list::dates;
+use iron_list::i18n::{self, Lang, Msg, tr, trf};
+use iron_list::remote::{Fetched, Remote};
+use iron_list::stats;
+use iron_list::{
+    EarliestN, Entry, FileFormat, Filter, Status, add_entries, added_by, annotation_tag,
+    annotations, append_entry, append_to_trash, archive_path, change_lines, completed_on, done_by,
+    entries_iter, entry_to_line, estimate_minutes, export, finished_on, import, inbox_path,
+    included_files, index, is_active, is_blocked, is_complete, is_priority, links, list_order,
+    move_entries, next_after_completion, next_entry, parse_entries, parse_input_line, project,
+    purge_trash, read_file, replace_file, review_date, rewrite_entry, roll_overdue, set_status,
+    set_tag_value, sort_file, status, top_level_tag, trash_path, visible_indices,
+    write_entry_sorted,
+};
+
+mod board;
+mod output;
+mod review;
+mod serve;
 
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Cli {
-    /// Path to todo file (default: ironlist.txt)
+    /// Path to todo file (default: ironlist.txt). Given more than once, `list`, `query`, `count`
+    /// and `complete` work on all the files together; other commands use the first
     #[arg(short, long, value_name = "FILE", default_value = "ironlist.txt")]
-    file: PathBuf,
+    file: Vec<PathBuf>,
     /// Persist a default file path and exit
     #[arg(long = "set-default", value_name = "PATH")]
     set_default: Option<PathBuf>,
@@ -22,6 +49,31 @@ struct Cli {
     #[arg(long = "show-all")]
     show_all: bool,
 
+    /// Copy the rendered output of `list`/`query` to the system clipboard instead of printing it
+    #[arg(long)]
+    copy: bool,
+
+    /// Never pipe long output through a pager
+    #[arg(long = "no-pager")]
+    no_pager: bool,
+
+    /// Language of the output (en, de, fr); overrides the `lang` setting and the locale
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Ignore the active context for this command
+    #[arg(long = "no-context")]
+    no_context: bool,
+
+    /// Print `list`/`query`/`next`/`random` results in the stable tab-delimited format
+    /// `id<TAB>date<TAB>status<TAB>tags<TAB>description` (see README) instead of tables
+    #[arg(long)]
+    porcelain: bool,
+
+    /// Refuse to run commands that change the data file (also the `read_only` setting)
+    #[arg(long = "read-only")]
+    read_only: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -29,22 +81,91 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// List all entries (numbered, sorted by date asc)
-    List {},
+    List {
+        /// Only show the first N entries; the file is streamed and only N entries are kept in memory
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Only show blocked entries: waiting, or tagged `blocked` (numbered like `query` results)
+        #[arg(long)]
+        blocked: bool,
+
+        /// Only show entries parked with the `someday` status (numbered for `activate`)
+        #[arg(long)]
+        someday: bool,
+    },
     /// Append a raw entry line to the todo file. The line should follow the expected format.
     Add {
-        /// The raw line to append (e.g. "YYYY-MM-DD    Description    tag1,tag2")
-        #[arg(value_name = "LINE")]
+        /// The raw line to append (e.g. "YYYY-MM-DD    Description    tag1,tag2"); the date may
+        /// be `-` for none
+        #[arg(value_name = "LINE", allow_hyphen_values = true)]
         line: String,
     },
     /// Edit an entry by its printed number (from `list`). Replacement_line must be a valid entry.
     Edit {
         /// 1-based index as shown in `list`
-        #[arg(value_name = "INDEX")]
-        index: usize,
+        #[arg(value_name = "INDEX", required_unless_present = "where_")]
+        index: Option<usize>,
 
         /// The replacement line (same format as `add`)
-        #[arg(value_name = "LINE")]
-        line: String,
+        #[arg(value_name = "LINE", required_unless_present = "where_")]
+        line: Option<String>,
+
+        /// Edit every entry matching the filter options instead of one numbered entry
+        #[arg(long = "where", conflicts_with_all = ["index", "line"], requires = "replace")]
+        where_: bool,
+
+        #[command(flatten)]
+        criteria: QueryArgs,
+
+        /// Substitution applied to the description of each matching entry, e.g. `/old/new/`
+        /// (any delimiter works: `|a/b|c/d|`)
+        #[arg(long, value_name = "/OLD/NEW/", requires = "where_")]
+        replace: Option<String>,
+
+        /// Apply a --where edit without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Add and/or remove tags on every entry matching the same filters as `query`
+    Retag {
+        #[command(flatten)]
+        criteria: QueryArgs,
+
+        /// Tag to add; can be passed multiple times
+        #[arg(long, value_name = "TAG", required_unless_present = "remove")]
+        add: Vec<String>,
+
+        /// Tag to remove; can be passed multiple times
+        #[arg(long, value_name = "TAG")]
+        remove: Vec<String>,
+    },
+    /// Move entries to another list file: one by its printed number, or all matching the tag,
+    /// project or status filters
+    Move {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX", conflicts_with_all = ["tag", "project", "status"])]
+        index: Option<usize>,
+
+        /// Tag filter; can be passed multiple times
+        #[arg(long, value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Match entries with ANY of the tags rather than all of them
+        #[arg(long)]
+        any: bool,
+
+        /// Only entries in this project
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+
+        /// Only entries with this status
+        #[arg(long, value_name = "STATUS", value_parser = parse_status)]
+        status: Option<Status>,
+
+        /// The file to move the entries to; it is created if missing
+        #[arg(long, value_name = "FILE")]
+        to: PathBuf,
     },
     /// Mark an entry (by printed number from `list`) as complete by adding the `complete` tag.
     Complete {
@@ -52,229 +173,568 @@ enum Commands {
         #[arg(value_name = "INDEX")]
         index: usize,
     },
+    /// Mark an entry as in progress (pinned to the top of `list`) by its printed number
+    #[command(alias = "begin")]
+    Start {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+
+        /// Also start tracking time; it is added up in a `spent:` tag when the entry leaves
+        /// in-progress
+        #[arg(long)]
+        track: bool,
+    },
+    /// Set an entry's status by its printed number: todo, in-progress, waiting, done or cancelled.
+    /// Without arguments, print a one-line summary such as `3 due · 1 overdue` for status bars
+    Status {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX", requires = "new_status")]
+        index: Option<usize>,
+        #[arg(value_name = "STATUS", value_parser = parse_status)]
+        new_status: Option<Status>,
+
+        /// How to print the summary
+        #[arg(long, value_enum, default_value = "plain", conflicts_with = "index")]
+        format: BarFormat,
+
+        /// Print the summary as a colored fragment for tmux's `status-right`; nothing when no
+        /// entry is due
+        #[arg(long, conflicts_with_all = ["index", "format"])]
+        tmux: bool,
+    },
     /// Query entries by date range and/or tags
     Query {
-        /// Start date YYYY-MM-DD (inclusive)
-        #[arg(long, value_name = "DATE")]
-        from: Option<String>,
+        #[command(flatten)]
+        criteria: QueryArgs,
+    },
+    /// Count entries matching the same filters as `query` (all open entries when none are given)
+    Count {
+        #[command(flatten)]
+        criteria: QueryArgs,
 
-        /// End date YYYY-MM-DD (inclusive)
-        #[arg(long, value_name = "DATE")]
-        to: Option<String>,
+        /// Print a breakdown such as `open=12 overdue=3 today=1 complete=5` instead of one number
+        #[arg(long)]
+        breakdown: bool,
+    },
+    /// Manage the optional sidecar index used to speed up `query` on large files
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+    /// Skip one occurrence of a repeating entry (by printed number from `list`), scheduling the
+    /// next one in its place
+    Skip {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+    },
+    /// Move one entry (by printed number from `list`) to another date; for a repeating entry only
+    /// this occurrence moves
+    Reschedule {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
 
-        /// Exact date YYYY-MM-DD (sets both from and to)
-        #[arg(long, value_name = "DATE")]
-        date: Option<String>,
+        /// The new date: a date, a keyword such as `tomorrow`, `friday` or `+1w`, or `-` for none
+        #[arg(value_name = "DATE", allow_hyphen_values = true)]
+        date: String,
+    },
+    /// Make a someday entry (by printed number from `list --someday`) active again
+    Activate {
+        /// 1-based index as shown in `list --someday`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+    },
+    /// Add a timestamped note to an entry (by printed number from `list`)
+    Annotate {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
 
+        /// The note, e.g. "called the plumber"
+        #[arg(value_name = "TEXT")]
+        text: String,
+    },
+    /// Print everything about an entry (by printed number from `list`)
+    Show {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+    },
+    /// Open a link in an entry (by printed number from `list`) with the default handler
+    Open {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+
+        /// Which link to open when the entry has several (asked otherwise)
+        #[arg(long, value_name = "N")]
+        link: Option<usize>,
+    },
+    /// Print the listed entries one per line for rofi, dmenu or wofi; with `--action`, act on
+    /// the line selected from them, read from stdin
+    Menu {
+        /// What to do with the selected entry
+        #[arg(long, value_enum)]
+        action: Option<MenuAction>,
+    },
+    /// Print an entry (by printed number from `list`) in a shareable form
+    Share {
+        /// 1-based index as shown in `list`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+
+        /// Render the entry as a QR code in the terminal (requires `qrencode`)
+        #[arg(long)]
+        qr: bool,
+    },
+    /// Export entries in another format (e.g. a printable daily sheet)
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "html")]
+        format: ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Add the entries of another IronList file, or of one published at a URL, to this one
+    Import {
+        /// The file to read entries from
+        #[arg(value_name = "FILE", required_unless_present = "url", conflicts_with = "url")]
+        path: Option<PathBuf>,
+
+        /// Download the entries from this https:// URL instead (IronList, todo.txt or iCalendar);
+        /// implies --merge
+        #[arg(long, value_name = "URL")]
+        url: Option<String>,
+
+        /// Skip entries that are already in the list (same `id:` tag, or same date and
+        /// description) and report added/skipped/conflicting counts
+        #[arg(long)]
+        merge: bool,
+    },
+    /// Show the single open entry to work on next (earliest due; `urgent`/`priority` tags win ties)
+    Next {},
+    /// Pick a random open entry, optionally limited to some tags
+    Random {
         /// Tag filter; can be passed multiple times
         #[arg(long, value_name = "TAG")]
         tag: Vec<String>,
 
-        /// If set, match entries that contain ANY of the provided tags (OR semantics).
-        /// By default the query requires ALL provided tags (AND semantics).
+        /// Match entries with ANY of the provided tags instead of all of them
         #[arg(long)]
         any: bool,
     },
-}
+    /// Kanban board: Backlog, In Progress (tag `in-progress`) and Done side by side
+    Board {
+        /// Use entries tagged TAG as a column instead, once per column in order (e.g.
+        /// `--column waiting --column review`)
+        #[arg(long = "column", value_name = "TAG")]
+        columns: Vec<String>,
 
-#[derive(Debug, Clone)]
-struct Entry {
-    date: NaiveDate,
-    desc: String,
-    tags: Vec<String>,
-    #[allow(dead_code)]
-    raw_line: String,
-}
+        /// Show at most N entries per column (the latest ones in Done)
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Per-project summary: open, overdue and completed entries for each `project:<name>`
+    Projects {},
+    /// Show the audit log: every change made to the data file, newest first
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+
+        /// Only show the N most recent changes
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Compare two list files: entries only in one of them, and entries in both that differ
+    Diff {
+        #[arg(value_name = "FILE_A")]
+        a: PathBuf,
+        #[arg(value_name = "FILE_B")]
+        b: PathBuf,
+    },
+    /// Move completed entries dated before a cutoff to the archive file (or the trash)
+    #[command(group(clap::ArgGroup::new("cutoff").required(true).args(["completed_before", "older_than"])))]
+    Prune {
+        /// Completed entries finished before this date
+        #[arg(long, value_name = "DATE", allow_hyphen_values = true)]
+        completed_before: Option<String>,
+
+        /// Completed entries finished longer ago than this, e.g. `90d`, `12w` or `6m`
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Move them to the trash instead of the archive
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Capture a thought in the inbox, undated and untagged, to sort out later with `inbox`
+    #[command(name = "in")]
+    In {
+        /// The thought, e.g. "call the bank about the card"
+        #[arg(value_name = "TEXT", required = true, num_args = 1.., allow_hyphen_values = true)]
+        text: Vec<String>,
+    },
+    /// List the inbox, or turn an inbox item into a task or drop it
+    Inbox {
+        #[command(subcommand)]
+        action: Option<InboxAction>,
+    },
+    /// Manage the trash file that deleted entries are moved to
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    /// Save a snapshot of the data file now (one is also taken after every change)
+    Snapshot {},
+    /// How many entries are in each state, per tag and how late, and with `--velocity` how many
+    /// get done over time
+    Stats {
+        /// Also show entries completed per day, week and month, with averages
+        #[arg(long)]
+        velocity: bool,
+
+        /// Print every metric, velocity included, as JSON for dashboards and scripts
+        #[arg(long)]
+        json: bool,
+    },
+    /// A terse segment such as `⚑3` counting due entries, for shell prompts; prints nothing
+    /// when fewer than the threshold are due
+    Prompt {
+        /// Fewest due entries to show the segment for [default: the `prompt_threshold` setting]
+        #[arg(long, value_name = "N")]
+        min: Option<usize>,
+
+        /// Also count entries due within this many days [default: the `prompt_days` setting]
+        #[arg(long, value_name = "DAYS")]
+        days: Option<u32>,
+    },
+    /// Serve the list read-only over HTTP, so calendar apps can subscribe to the due dates
+    Serve {
+        /// Serve open dated entries as an iCalendar feed at `/ironlist.ics`
+        #[arg(long, required = true)]
+        ics: bool,
+
+        /// Address and port to listen on; use `0.0.0.0:PORT` to be reachable from other devices
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8088")]
+        listen: String,
+
+        /// Only serve the feed to requests with `?token=TOKEN` in the URL
+        #[arg(long, value_name = "TOKEN")]
+        token: Option<String>,
+    },
+    /// Open entries nobody has changed for a while, oldest first (from the audit log)
+    Stale {
+        /// Only entries untouched for more than this many days
+        #[arg(long, default_value_t = STALE_DAYS)]
+        days: u32,
+    },
+    /// Per-person summary for shared lists: open entries each person added and entries each
+    /// person completed (see the `attribution` setting)
+    People {},
+    /// Walk through overdue entries one at a time and reschedule, complete, delete or keep each
+    Review {
+        #[command(subcommand)]
+        action: Option<ReviewAction>,
+    },
+    /// Spread overdue and overloaded open entries over the coming days, after a preview
+    Plan {
+        /// Plan entries due within this many days from today (overdue ones included)
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+
+        /// Most entries on one day [default: the `day_capacity` setting, or 5]
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        per_day: Option<u32>,
 
-fn is_complete(e: &Entry) -> bool {
-    e.tags.iter().any(|t| t.eq_ignore_ascii_case("complete"))
+        /// Most estimated hours of work on one day (from `est:` tags such as `est:30m` or `est:2h`)
+        /// [default: the `day_hours` setting]
+        #[arg(long)]
+        hours: Option<f64>,
+
+        /// Also give entries without a date one
+        #[arg(long)]
+        undated: bool,
+
+        /// Apply the new dates without asking
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Move overdue open entries to today's date
+    Roll {
+        /// Tag moved entries with `rolled:<n>`, counting how often each was rolled (always on with
+        /// the `roll_mark` setting)
+        #[arg(long)]
+        mark: bool,
+    },
+    /// Reorder the data file by date (run once after enabling `sorted_writes`)
+    Sort {},
+    /// Write the entries into one new file per top-level tag; the data file is left as is
+    Split {
+        /// Group entries by their first tag (currently the only way to split)
+        #[arg(long, required = true)]
+        by_tag: bool,
+
+        /// Directory for the new files; created if missing
+        #[arg(long, value_name = "DIR")]
+        out_dir: PathBuf,
+    },
+    /// Show or change persistent settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Set, clear or show the active context: tags that scope list, query, count, next, random
+    /// and export until changed
+    Context {
+        #[command(subcommand)]
+        action: Option<ContextAction>,
+    },
 }
 
-/// Return indices (into the original entries slice) for the entries that should be visible
-/// given the `show_all` flag.
-fn visible_indices(entries: &[Entry], show_all: bool) -> Vec<usize> {
-    if show_all {
-        (0..entries.len()).collect()
-    } else {
-        entries
-            .iter()
-            .enumerate()
-            .filter(|(_, e)| !is_complete(e))
-            .map(|(i, _)| i)
-            .collect()
-    }
+/// Date and tag criteria shared by `query` and the commands that filter like it.
+#[derive(Args)]
+struct QueryArgs {
+    /// Start date (inclusive): YYYY-MM-DD, the `date_format` setting or a keyword like `today`
+    #[arg(long, value_name = "DATE", allow_hyphen_values = true)]
+    from: Option<String>,
+
+    /// End date (inclusive): YYYY-MM-DD, the `date_format` setting or a keyword like `friday`
+    #[arg(long, value_name = "DATE", allow_hyphen_values = true)]
+    to: Option<String>,
+
+    /// Exact date (sets both from and to): YYYY-MM-DD, the `date_format` setting or a keyword
+    #[arg(long, value_name = "DATE", allow_hyphen_values = true)]
+    date: Option<String>,
+
+    /// Tag filter; can be passed multiple times
+    #[arg(long, value_name = "TAG")]
+    tag: Vec<String>,
+
+    /// If set, match entries that contain ANY of the provided tags (OR semantics).
+    /// By default the query requires ALL provided tags (AND semantics).
+    #[arg(long)]
+    any: bool,
+
+    /// Only entries in this project (their `project:<name>` tag)
+    #[arg(long, value_name = "NAME")]
+    project: Option<String>,
+
+    /// Only entries with this status: todo, in-progress, waiting, done or cancelled
+    #[arg(long, value_name = "STATUS", value_parser = parse_status)]
+    status: Option<Status>,
+
+    /// Only entries added or completed by this person (their `by:` or `done-by:` tag)
+    #[arg(long, value_name = "NAME")]
+    by: Option<String>,
+
+    /// Only open entries dated before today
+    #[arg(long)]
+    overdue: bool,
 }
 
-fn parse_line(line: &str) -> Option<Entry> {
-    // Expected format: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2
-    // Also accept runs of 4+ spaces as a separator because many shells don't accept literal tabs.
-    let parts: Vec<&str> = split_on_tab_or_spaces(line);
-    if parts.len() < 2 {
+/// Split a substitution such as `/old/new/` into its search and replacement text. The first
+/// character is the delimiter; the trailing one may be left out. The search text must not be
+/// empty, and neither part may contain a tab.
+fn parse_substitution(s: &str) -> Option<(&str, &str)> {
+    let delim = s.chars().next()?;
+    let body = &s[delim.len_utf8()..];
+    let (from, to) = body.strip_suffix(delim).unwrap_or(body).split_once(delim)?;
+    if from.is_empty() || to.contains(delim) || from.contains('\t') || to.contains('\t') {
         return None;
     }
-    let date = NaiveDate::parse_from_str(parts[0].trim(), "%Y-%m-%d").ok()?;
-    let desc = parts[1].trim().to_string();
-    let tags = if parts.len() >= 3 {
-        parts[2]
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    } else {
-        Vec::new()
-    };
-    Some(Entry {
-        date,
-        desc,
-        tags,
-        raw_line: line.to_string(),
+    Some((from, to))
+}
+
+/// File name (without extension) for the entries of `tag` in `split`: lowercase, with anything
+/// but letters, digits, `-` and `_` replaced by `_`.
+fn split_file_stem(tag: &str) -> String {
+    tag.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Clap value parser for status names.
+fn parse_status(s: &str) -> Result<Status, String> {
+    Status::parse(s).ok_or_else(|| {
+        let names: Vec<&str> = Status::ALL.iter().map(|st| st.name()).collect();
+        format!("expected one of {}", names.join(", "))
     })
 }
 
-/// Split a line into fields using either tab characters or runs of 4+ spaces as separators.
-fn split_on_tab_or_spaces(s: &str) -> Vec<&str> {
-    let bytes = s.as_bytes();
-    let mut parts = Vec::new();
-    let mut start = 0usize;
-    let mut i = 0usize;
-    while i < bytes.len() {
-        match bytes[i] {
-            b'\t' => {
-                // separator at i
-                parts.push(s[start..i].trim());
-                i += 1;
-                start = i;
-            }
-            b' ' => {
-                // count run of spaces
-                let mut j = i;
-                while j < bytes.len() && bytes[j] == b' ' {
-                    j += 1;
-                }
-                if j - i >= 4 {
-                    // treat as separator
-                    parts.push(s[start..i].trim());
-                    // skip all spaces
-                    i = j;
-                    start = i;
-                    continue;
-                } else {
-                    // not a separator, continue
-                    i = j;
-                    continue;
-                }
-            }
-            _ => {
-                i += 1;
-            }
-        }
+impl QueryArgs {
+    fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none() && self.date.is_none() && self.tag.is_empty() && self.project.is_none() && self.status.is_none() && self.by.is_none() && !self.overdue
     }
-    // push remainder
-    if start <= s.len() {
-        parts.push(s[start..].trim());
+
+    /// Build the filter, resolving date keywords such as `today` or `friday` against `today`.
+    /// Fails after printing an error on a date that can't be parsed.
+    fn into_filter(self, today: NaiveDate) -> io::Result<Filter> {
+        let parse = |s: Option<String>| {
+            s.map(|s| {
+                dates::parse_relative(&s, today).ok_or_else(|| {
+                    eprintln!("{}", trf(Msg::InvalidDate, &[&s]));
+                    failed(1)
+                })
+            })
+            .transpose()
+        };
+        // If exact date provided, it overrides from/to
+        let (from, to) = if self.date.is_some() {
+            let parsed = parse(self.date)?;
+            (parsed, parsed)
+        } else {
+            (parse(self.from)?, parse(self.to)?)
+        };
+        let yesterday = today - chrono::Duration::days(1);
+        let to = if self.overdue { Some(to.map_or(yesterday, |to| to.min(yesterday))) } else { to };
+        Ok(Filter {
+            from,
+            to,
+            tags: self.tag,
+            any: self.any,
+            project: self.project,
+            status: self.status,
+            by: self.by,
+            open: self.overdue,
+        })
     }
-    // filter out empty parts that may occur
-    parts.into_iter().filter(|p| !p.is_empty()).collect()
 }
 
-fn read_entries(path: &PathBuf) -> io::Result<Vec<Entry>> {
-    let f = File::open(path)?;
-    let reader = BufReader::new(f);
-    let mut entries = Vec::new();
-    for (i, line) in reader.lines().enumerate() {
-        match line {
-            Ok(l) => match parse_line(&l) {
-                Some(e) => entries.push(e),
-                None => eprintln!("Skipping malformed line {}: {}", i + 1, l),
-            },
-            Err(err) => eprintln!("Error reading line {}: {}", i + 1, err),
-        }
-    }
-    Ok(entries)
+#[derive(Clone, Copy, ValueEnum)]
+enum MenuAction {
+    /// Mark the entry done
+    Complete,
+    /// Open the entry's first link
+    Open,
+    /// Open the data file at the entry's line in `$VISUAL` or `$EDITOR`
+    Edit,
 }
 
-fn append_entry(path: &PathBuf, line: &str) -> io::Result<()> {
-    use std::fs::OpenOptions;
-    use std::io::Write;
+#[derive(Clone, Copy, ValueEnum)]
+enum BarFormat {
+    /// The summary text alone
+    Plain,
+    /// The summary text with its counts, as one line of JSON
+    Json,
+    /// JSON for a waybar `custom` module (`"return-type": "json"`), with a tooltip and a class
+    Waybar,
+}
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// One-page, print-friendly agenda of today's and overdue open tasks with checkboxes
+    Html,
+    /// Every entry in the current context, with its annotations
+    Json,
+    /// Open dated entries as all-day events in an iCalendar (`.ics`) file
+    Ics,
+}
 
-    let mut f = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)?;
-    f.write_all(line.as_bytes())?;
-    f.write_all(b"\n")?;
-    Ok(())
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print every setting with its current value and the config file location
+    Show {},
+    /// Persist a setting, e.g. `config set sorted_writes true`
+    Set {
+        #[arg(value_name = "KEY")]
+        key: String,
+        #[arg(value_name = "VALUE")]
+        value: String,
+    },
 }
 
-fn write_entries_to_file(path: &PathBuf, entries: &[Entry]) -> io::Result<()> {
-    use std::fs::OpenOptions;
-    use std::io::Write;
+#[derive(Subcommand)]
+enum ContextAction {
+    /// Only show entries tagged with any of TAGS, e.g. `context set work`
+    Set {
+        #[arg(value_name = "TAGS", required = true)]
+        tags: Vec<String>,
+    },
+    /// Clear the active context
+    None {},
+    /// Print the active context (the default)
+    Show {},
+}
 
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// List the snapshots, or print snapshot N
+    Show {
+        #[arg(value_name = "N")]
+        number: Option<u32>,
+    },
+    /// Compare snapshot A with snapshot B (or with the current file when B is left out)
+    Diff {
+        #[arg(value_name = "A")]
+        a: u32,
+        #[arg(value_name = "B")]
+        b: Option<u32>,
+    },
+    /// Put the content of snapshot N back into the data file
+    Restore {
+        #[arg(value_name = "N")]
+        number: u32,
+    },
+}
 
-    let mut f = OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
-    for e in entries {
-        let line = entry_to_line(e);
-        f.write_all(line.as_bytes())?;
-        f.write_all(b"\n")?;
-    }
-    Ok(())
+#[derive(Subcommand)]
+enum ReviewAction {
+    /// List open entries whose `review:YYYY-MM-DD` date has come (numbered like `query` results)
+    Due {},
 }
 
-fn entry_to_line(e: &Entry) -> String {
-    let tag_str = if e.tags.is_empty() { String::new() } else { e.tags.join(",") };
-    if tag_str.is_empty() {
-        format!("{}\t{}", e.date.format("%Y-%m-%d"), e.desc)
-    } else {
-        format!("{}\t{}\t{}", e.date.format("%Y-%m-%d"), e.desc, tag_str)
-    }
+#[derive(Subcommand)]
+enum InboxAction {
+    /// Move an inbox item (by its number in `inbox`) to the list as a task
+    File {
+        /// 1-based index as shown in `inbox`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+
+        /// Due date: a date or a keyword such as `tomorrow` or `friday` (undated without it)
+        #[arg(long, value_name = "DATE", allow_hyphen_values = true)]
+        date: Option<String>,
+
+        /// Tag to give the task (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+    /// Delete an inbox item (by its number in `inbox`)
+    Drop {
+        /// 1-based index as shown in `inbox`
+        #[arg(value_name = "INDEX")]
+        index: usize,
+    },
 }
 
-fn filter_by_date_range(entries: Vec<Entry>, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Vec<Entry> {
-    entries
-        .into_iter()
-        .filter(|e| {
-            if let Some(f) = from {
-                if e.date < f {
-                    return false;
-                }
-            }
-            if let Some(t) = to {
-                if e.date > t {
-                    return false;
-                }
-            }
-            true
-        })
-        .collect()
+#[derive(Subcommand)]
+enum TrashAction {
+    /// Permanently remove deleted entries from the trash
+    Purge {
+        /// Only entries deleted longer ago than this, e.g. `7d`, `2w` or `3m`
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+    },
 }
 
-fn filter_by_tags(entries: Vec<Entry>, tags: &[String], any: bool) -> Vec<Entry> {
-    if tags.is_empty() {
-        return entries;
-    }
-    if any {
-        // OR semantics: entry must match at least one tag (case-insensitive)
-        entries
-            .into_iter()
-            .filter(|e| tags.iter().any(|q| e.tags.iter().any(|et| et.eq_ignore_ascii_case(q))))
-            .collect()
-    } else {
-        // AND semantics: entry must contain all query tags (case-insensitive)
-        entries
-            .into_iter()
-            .filter(|e| tags.iter().all(|q| e.tags.iter().any(|et| et.eq_ignore_ascii_case(q))))
-            .collect()
-    }
+#[derive(Subcommand)]
+enum IndexAction {
+    /// Build (or rebuild) the index next to the data file
+    Rebuild {},
+    /// Delete the index; `query` falls back to reading the whole file
+    Remove {},
+}
+
+fn render_numbered(out: &mut String, entries: &[&Entry<'_>]) {
+    render_numbered_from(out, entries, 1);
 }
 
-fn print_numbered(entries: &[Entry]) {
+/// Like `render_numbered`, but numbering the rows starting at `first`.
+fn render_numbered_from(out: &mut String, entries: &[&Entry<'_>], first: usize) {
     // Table columns:
     // No. (right-aligned width 3) | Date (10) | Task (30, wrapped) | Tags (rest)
     const NUM_AREA: usize = 5; // e.g. "  1. " length
@@ -282,48 +742,107 @@ fn print_numbered(entries: &[Entry]) {
     const TAG_W: usize = 20;
 
     // Header
-    println!("{:>3}  {:10}  {:30}  {:<width$}", "No", "Date", "Task", "Tags", width = TAG_W);
+    writeln!(
+        out,
+        "{:>3}  {:10}  {:30}  {:<width$}",
+        tr(Msg::ColNo),
+        tr(Msg::ColDate),
+        tr(Msg::ColTask),
+        tr(Msg::ColTags),
+        width = TAG_W
+    ).ok();
     // underline: dashes matching each column width (tags column uses TAG_W)
     let tag_underline = "-".repeat(TAG_W);
-    println!("{:->3}  {:->10}  {:->30}  {}", "", "", "", tag_underline);
+    writeln!(out, "{:->3}  {:->10}  {:->30}  {}", "", "", "", tag_underline).ok();
 
     for (i, e) in entries.iter().enumerate() {
         let tag_str = if e.tags.is_empty() { String::from("-") } else { e.tags.join(",") };
 
-        let date_str = e.date.format("%Y-%m-%d").to_string();
-        let wrapped = wrap_text(&e.desc, TASK_W);
+        let date_str = dates::display(e.date);
+        // statuses other than plain todo and done get a badge in front of the task
+        let badge = match status(e) {
+            Status::Todo | Status::Done => is_blocked(e).then_some("blocked"),
+            st => Some(st.name()),
+        };
+        let task = match badge {
+            Some(badge) => format!("[{}] {}", badge, e.desc),
+            None => e.desc.to_string(),
+        };
+        let wrapped = wrap_text(&task, TASK_W);
+        // blocked entries are dimmed on a terminal
+        let (on, off) = if is_blocked(e) { output::dim() } else { ("", "") };
 
         for (line_idx, task_line) in wrapped.iter().enumerate() {
             if line_idx == 0 {
                 // first line: print number, date, first task part, tags
-                println!("{:>3}. {:10}  {:30}  {:<width$}", i + 1, date_str, task_line, tag_str, width = TAG_W);
+                writeln!(out, "{}{:>3}. {:10}  {:30}  {:<width$}{}", on, first + i, date_str, task_line, tag_str, off, width = TAG_W).ok();
             } else {
                 // continuation lines: blank number and date columns
                 let spacer = " ".repeat(NUM_AREA);
-                println!("{}{:10}  {:30}  {:<width$}", spacer, "", task_line, "", width = TAG_W);
+                writeln!(out, "{}{}{:10}  {:30}  {:<width$}{}", on, spacer, "", task_line, "", off, width = TAG_W).ok();
             }
         }
         // if description was empty, still print a line
         if wrapped.is_empty() {
-            println!("{:>3}. {:10}  {:30}  {}", i + 1, date_str, "", tag_str);
+            writeln!(out, "{}{:>3}. {:10}  {:30}  {}{}", on, first + i, date_str, "", tag_str, off).ok();
         }
     }
 }
 
-fn print_titled_tables(all_entries: &[Entry], show_all: bool) {
-    // First table: incomplete entries
-    let incomplete: Vec<Entry> = all_entries.iter().filter(|e| !is_complete(e)).cloned().collect();
-    print_numbered(&incomplete);
+/// Render `entries` in the `--porcelain` format: one line per entry, no header. This format is
+/// a stable interface for scripts; do not change it, only the human tables may evolve.
+fn render_porcelain(out: &mut String, entries: &[&Entry<'_>]) {
+    for e in entries {
+        writeln!(out, "{}", porcelain_line(e)).ok();
+    }
+}
 
-    // If requested, print completed entries in a second table below
-    if show_all {
-        let completed: Vec<Entry> = all_entries.iter().filter(|e| is_complete(e)).cloned().collect();
-        if !completed.is_empty() {
-            println!("");
-            println!("Completed:");
-            print_numbered(&completed);
+/// The five `--porcelain` fields of `e`, without the line break.
+fn porcelain_line(e: &Entry<'_>) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        e.line.map_or(0, |l| l + 1),
+        dates::store(e.date),
+        if is_complete(e) { "complete" } else { "open" },
+        e.tags.join(","),
+        e.desc
+    )
+}
+
+/// Render the open entries, then (with `show_all`) the completed ones. Returns how many entries
+/// were rendered.
+fn render_titled_tables<'a>(
+    out: &mut String,
+    all_entries: impl IntoIterator<Item = &'a Entry<'a>>,
+    show_all: bool,
+    porcelain: bool,
+) -> usize {
+    // Split into incomplete and completed in one pass, borrowing rather than cloning
+    let (completed, incomplete): (Vec<&Entry<'_>>, Vec<&Entry<'_>>) = all_entries.into_iter().partition(|e| is_complete(e));
+    if porcelain {
+        render_porcelain(out, &incomplete);
+        if show_all {
+            render_porcelain(out, &completed);
         }
+        return incomplete.len() + if show_all { completed.len() } else { 0 };
+    }
+
+    // First table: incomplete entries, those without a date under their own heading
+    let (undated, dated): (Vec<&Entry<'_>>, Vec<&Entry<'_>>) = incomplete.iter().partition(|e| e.date == dates::UNDATED);
+    render_numbered(out, &dated);
+    if !undated.is_empty() {
+        writeln!(out).ok();
+        writeln!(out, "{}", tr(Msg::NoDateHeading)).ok();
+        render_numbered_from(out, &undated, dated.len() + 1);
     }
+
+    // If requested, print completed entries in a second table below
+    if show_all && !completed.is_empty() {
+        writeln!(out).ok();
+        writeln!(out, "{}", tr(Msg::CompletedHeading)).ok();
+        render_numbered(out, &completed);
+    }
+    incomplete.len() + if show_all { completed.len() } else { 0 }
 }
 
 /// Simple word-wrap helper: splits on whitespace and builds lines of maximum `width` characters.
@@ -382,8 +901,84 @@ fn wrap_text(s: &str, width: usize) -> Vec<String> {
     lines
 }
 
-fn main() -> io::Result<()> {
-    let cli = Cli::parse();
+/// How many days an open entry may go unchanged before `stale` and `review` bring it up.
+const STALE_DAYS: u32 = 30;
+
+/// Exit status of `query` when nothing matched, distinct from the status 1 used for errors.
+const EXIT_NO_MATCH: u8 = 2;
+
+/// A command that failed after printing why, ending [`run`] with the exit status it carries.
+/// Leaving through [`main`] instead of `process::exit` still uploads a remote data file.
+#[derive(Debug)]
+struct Failed(u8);
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
+
+impl std::error::Error for Failed {}
+
+/// The error for [`run`] to return when it already printed why it failed.
+fn failed(status: u8) -> io::Error {
+    io::Error::other(Failed(status))
+}
+
+/// A remote data file (`--file ssh://...` or `s3://...`), its local working copy and what was fetched into it.
+static REMOTE: OnceLock<(Remote, PathBuf, Fetched)> = OnceLock::new();
+
+fn main() -> ExitCode {
+    let status = match run() {
+        Ok(()) => 0,
+        Err(err) => match err.get_ref().and_then(|e| e.downcast_ref::<Failed>()) {
+            Some(Failed(status)) => *status,
+            None => {
+                eprintln!("Error: {}", err);
+                1
+            }
+        },
+    };
+    // whatever the command wrote, also before it failed or found nothing, goes to the remote file
+    if let Err(err) = upload_remote() {
+        eprintln!("Error: {}", err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::from(status)
+}
+
+/// Fetch the remote data file at `remote` into its local working copy and return that path.
+fn fetch_remote(remote: Remote) -> io::Result<PathBuf> {
+    let local = remote.cache_path();
+    let fetched = remote.download()?;
+    if let Some(parent) = local.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&local, &fetched.content)?;
+    REMOTE.set((remote, local.clone(), fetched)).ok();
+    Ok(local)
+}
+
+/// Whether the data file is remote. Its inbox, archive, trash, audit log, snapshots, backups and
+/// index would only live next to the local working copy and never be uploaded, so it has none.
+pub(crate) fn is_remote() -> bool {
+    REMOTE.get().is_some()
+}
+
+/// Upload the working copy of a remote data file if the command changed it.
+fn upload_remote() -> io::Result<()> {
+    let Some((remote, local, fetched)) = REMOTE.get() else {
+        return Ok(());
+    };
+    let content = std::fs::read(local)?;
+    if content != fetched.content {
+        remote.upload(&content, fetched)?;
+    }
+    Ok(())
+}
+
+fn run() -> io::Result<()> {
+    let mut cli = Cli::parse();
     // If the user asked to show the saved default, print and exit.
     if cli.show_default {
         if let Some(p) = read_saved_default() {
@@ -426,108 +1021,1874 @@ fn main() -> io::Result<()> {
         return Ok(());
     }
 
-    // Determine the data file path. If the user passed an explicit --file that exists, prefer it.
-    // Otherwise consult the persisted default (or ask the user on first run).
-    let file_path = if cli.file.as_os_str() != "ironlist.txt" && cli.file.exists() {
-        cli.file.clone()
-    } else {
-        get_or_ask_default_file()?
-    };
-    let mut entries = read_entries(&file_path)?;
-
-    // sort by date ascending
-    entries.sort_by_key(|e| e.date);
-
-    match cli.command {
-        None | Some(Commands::List {}) => {
-            // Print incomplete entries first; if --show-all, show completed entries in a second table
-            print_titled_tables(&entries, cli.show_all);
+    // Settings commands don't touch the data file, so handle them before resolving it.
+    match &cli.command {
+        Some(Commands::Config { action: ConfigAction::Show {} }) => {
+            let config = Config::load();
+            println!("Config file: {}", config::config_path().display());
+            for key in config::KEYS {
+                println!("{} = {}", key, config.get(key).unwrap_or_default());
+            }
+            return Ok(());
         }
-        Some(Commands::Query { from, to, date, tag, any }) => {
-            // Require at least one criterion (date range, exact date, or tag)
-            if from.is_none() && to.is_none() && date.is_none() && tag.is_empty() {
-                eprintln!("Query requires at least one of --from, --to, --date or --tag");
-                std::process::exit(1);
+        Some(Commands::Config { action: ConfigAction::Set { key, value } }) => {
+            if let Err(err) = config::set(key, value) {
+                eprintln!("Could not save setting: {}", err);
+                return Err(failed(1));
             }
+            println!("Saved {} = {} to {}", key, value, config::config_path().display());
+            return Ok(());
+        }
+        Some(Commands::Context { action }) => {
+            let tags = match action {
+                Some(ContextAction::Set { tags }) => tags.join(","),
+                Some(ContextAction::None {}) => String::new(),
+                Some(ContextAction::Show {}) | None => {
+                    let context = Config::load().context;
+                    if context.is_empty() {
+                        println!("{}", tr(Msg::NoContext));
+                    } else {
+                        println!("{}", trf(Msg::ActiveContext, &[&context.join(",")]));
+                    }
+                    return Ok(());
+                }
+            };
+            if let Err(err) = config::set("context", &tags) {
+                eprintln!("Could not save setting: {}", err);
+                return Err(failed(1));
+            }
+            if tags.is_empty() {
+                println!("{}", tr(Msg::NoContext));
+            } else {
+                println!("{}", trf(Msg::ActiveContext, &[&tags]));
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let config = Config::load();
+    let lang = match &cli.lang {
+        Some(code) => match Lang::parse(code) {
+            Some(lang) => Some(lang),
+            None => {
+                let codes: Vec<&str> = Lang::ALL.iter().map(|l| l.code()).collect();
+                eprintln!("Unknown language `{}`; available: {}", code, codes.join(", "));
+                return Err(failed(1));
+            }
+        },
+        None => config.lang.or_else(Lang::from_env),
+    };
+    i18n::set_lang(lang.unwrap_or(Lang::En));
+    // validated when loaded, so this only fails if the config file was edited badly by hand
+    if let Err(msg) = dates::set_format(&config.date_format) {
+        eprintln!("Ignoring date_format: {}", msg);
+    }
+    let clock = Clock::new(config.timezone);
+    let paging = config.paging && !cli.no_pager && !cli.porcelain;
+    output::set_color(!cli.copy && !cli.porcelain);
+
+    // `diff` works on the two files it is given, not on the data file.
+    if let Some(Commands::Diff { a, b }) = &cli.command {
+        let (content_a, content_b) = (read_file(a)?, read_file(b)?);
+        let (entries_a, entries_b) = (parse_entries(&content_a, FileFormat::of(a)), parse_entries(&content_b, FileFormat::of(b)));
+        let cmp = import::compare(&entries_a, &entries_b);
+        let mut out = String::new();
+        for (path, only) in [(a, &cmp.only_a), (b, &cmp.only_b)] {
+            if !only.is_empty() {
+                writeln!(out, "{}", trf(Msg::OnlyIn, &[&path.display()])).ok();
+                for e in only {
+                    writeln!(out, "    {}", entry_summary(e)).ok();
+                }
+            }
+        }
+        if !cmp.changed.is_empty() {
+            writeln!(out, "{}", tr(Msg::Differing)).ok();
+            for (x, y) in &cmp.changed {
+                writeln!(out, "  - {}", entry_summary(x)).ok();
+                writeln!(out, "  + {}", entry_summary(y)).ok();
+            }
+        }
+        if out.is_empty() {
+            println!("{}", tr(Msg::NoDifferences));
+        } else {
+            output::emit(&out, cli.copy, paging)?;
+        }
+        return Ok(());
+    }
+
+    // Determine the data file path. If the user passed an explicit --file that exists, prefer it.
+    // Otherwise consult the persisted default (or ask the user on first run).
+    let mut file_path = if let Some(remote) = cli.file[0].to_str().and_then(Remote::parse) {
+        fetch_remote(remote).map_err(|err| {
+            eprintln!("Could not fetch {}: {}", cli.file[0].display(), err);
+            failed(1)
+        })?
+    } else if cli.file[0].as_os_str() != "ironlist.txt" && cli.file[0].exists() {
+        cli.file[0].clone()
+    } else {
+        get_or_ask_default_file()?
+    };
+
+    // The active context narrows every listing to entries carrying one of its tags.
+    let context = Filter {
+        tags: if cli.no_context { Vec::new() } else { config.context.clone() },
+        any: true,
+        ..Filter::default()
+    };
+
+    // With more files, given or `#include`d, `list` numbers their entries together (see
+    // `run_merged`); a command picking an entry by that number acts on the file it is in.
+    let more = if cli.file.len() > 1 { &cli.file[1..] } else { &config.files[..] };
+    let someday = matches!(cli.command, Some(Commands::Activate { .. }));
+    if let Some(index) = picked_index(&mut cli.command) {
+        let view = merged_view(&file_path, more)?;
+        if view.len() > 1 {
+            let contents = view.iter().map(|f| read_file(f)).collect::<io::Result<Vec<String>>>()?;
+            let (sources, entries) = merge_entries(&view, &contents);
+            let listed: Vec<usize> = if someday {
+                (0..entries.len()).filter(|&i| status(&entries[i]) == Status::Someday && context.matches(&entries[i])).collect()
+            } else {
+                listed_indices(&entries, cli.show_all, &context)
+            };
+            let Some((file, local)) = split_merged_index(&sources, &listed, *index) else {
+                eprintln!("{}", trf(Msg::IndexOutOfRange, &[index, &listed.len()]));
+                return Err(failed(1));
+            };
+            *index = local;
+            file_path = view[file].clone();
+        }
+    }
+    let file_format = FileFormat::of(&file_path);
+
+    // With auto_roll the list is a daily plan: overdue entries move to today before being shown.
+    let shows_entries = matches!(
+        cli.command,
+        None | Some(
+            Commands::List { .. }
+                | Commands::Query { .. }
+                | Commands::Count { .. }
+                | Commands::Next {}
+                | Commands::Random { .. }
+                | Commands::Export { .. }
+                | Commands::Projects {}
+                | Commands::People {}
+                | Commands::Board { .. }
+        )
+    );
+    // In read-only mode nothing may write to the data file or its index.
+    let read_only = cli.read_only || config.read_only;
+    let mutates = matches!(
+        cli.command,
+        Some(
+            Commands::Add { .. }
+                | Commands::Edit { .. }
+                | Commands::Retag { .. }
+                | Commands::Move { .. }
+                | Commands::Complete { .. }
+                | Commands::Status { index: Some(_), .. }
+                | Commands::Start { .. }
+                | Commands::Import { .. }
+                | Commands::Review { action: None }
+                | Commands::Roll { .. }
+                | Commands::Sort {}
+                | Commands::Index { .. }
+                | Commands::History { action: Some(HistoryAction::Restore { .. }), .. }
+                | Commands::Snapshot {}
+                | Commands::Trash { .. }
+                | Commands::Prune { .. }
+                | Commands::Annotate { .. }
+                | Commands::Skip { .. }
+                | Commands::Reschedule { .. }
+                | Commands::Plan { .. }
+                | Commands::Activate { .. }
+                | Commands::In { .. }
+                | Commands::Inbox { action: Some(_) }
+                | Commands::Menu { action: Some(MenuAction::Complete | MenuAction::Edit) }
+        )
+    );
+    if read_only && mutates {
+        eprintln!("{}", trf(Msg::ReadOnly, &[&file_path.display()]));
+        return Err(failed(1));
+    }
+    let sidecar = match &cli.command {
+        Some(Commands::In { .. }) => Some("in"),
+        Some(Commands::Inbox { .. }) => Some("inbox"),
+        Some(Commands::Prune { .. }) => Some("prune"),
+        Some(Commands::Trash { .. }) => Some("trash"),
+        Some(Commands::History { .. }) => Some("history"),
+        Some(Commands::Snapshot {}) => Some("snapshot"),
+        Some(Commands::Index { .. }) => Some("index"),
+        _ => None,
+    };
+    if let Some(command) = sidecar.filter(|_| is_remote()) {
+        eprintln!("{}", trf(Msg::RemoteSidecar, &[&command]));
+        return Err(failed(1));
+    }
+
+    // Changes to the data file are recorded in its audit log and snapshots (see `history`).
+    let who = config.user().unwrap_or_else(|| "-".to_string());
+    let current = || read_file(&file_path).unwrap_or_default();
+
+    if config.auto_roll && shows_entries && !read_only {
+        let before = current();
+        roll(&file_path, &clock, config.roll_mark, config.sorted_writes)?;
+        record_change(&file_path, &before, &current(), &clock, &who, "roll (auto_roll)", config.snapshot_keep);
+    }
+    // Deleted entries only stay in the trash for `trash_retention_days`.
+    if config.trash_retention_days > 0 && !read_only && !is_remote() {
+        let cutoff = clock.today() - chrono::Duration::days(config.trash_retention_days.into());
+        if let Err(err) = purge_trash(&file_path, Some(cutoff)) {
+            eprintln!("Could not purge the trash {}: {}", trash_path(&file_path).display(), err);
+        }
+    }
+    // With more files, given or `#include`d, the listing commands show them all together.
+    if matches!(
+        cli.command,
+        None | Some(Commands::List { .. } | Commands::Query { .. } | Commands::Count { .. } | Commands::Next {} | Commands::Random { .. })
+    ) {
+        let more = if cli.file.len() > 1 { &cli.file[1..] } else { &config.files[..] };
+        let view = merged_view(&file_path, more)?;
+        if view.len() > 1 {
+            return run_merged(cli, &view, &clock, &context, paging);
+        }
+    }
+    let before = mutates.then(current);
+
+    match cli.command {
+        None | Some(Commands::List { limit: None, .. }) => {
+            let blocked = matches!(cli.command, Some(Commands::List { blocked: true, .. }));
+            let someday = matches!(cli.command, Some(Commands::List { someday: true, .. }));
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            // Print incomplete entries first; if --show-all, show completed entries in a second table
+            let mut out = String::new();
+            let shown = entries
+                .iter()
+                .filter(|e| context.matches(e) && (!blocked || is_blocked(e)) && someday_shown(e, someday, cli.show_all));
+            render_titled_tables(&mut out, shown, cli.show_all, cli.porcelain);
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::List { limit: Some(n), blocked, someday }) => {
+            // Stream the file, keeping only the first n entries of each table in list order, so
+            // the rows are numbered as in the full listing. The whole file is read even when it is
+            // kept sorted: an in-progress entry further down still goes to the top.
+            let mut incomplete = EarliestN::new(n);
+            let mut completed = EarliestN::new(n);
+            let wanted = |e: &Entry| context.matches(e) && (!blocked || is_blocked(e)) && someday_shown(e, someday, cli.show_all);
+            for e in entries_iter(&file_path)?.filter(wanted) {
+                if !is_complete(&e) {
+                    incomplete.push(e);
+                } else if cli.show_all {
+                    completed.push(e);
+                }
+            }
+            let mut shown = incomplete.into_sorted();
+            let remaining = n - shown.len();
+            shown.extend(completed.into_sorted().into_iter().take(remaining));
+            let mut out = String::new();
+            render_titled_tables(&mut out, &shown, cli.show_all, cli.porcelain);
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Query { criteria }) => {
+            // Require at least one criterion (date range, exact date, or tag)
+            if criteria.is_empty() {
+                eprintln!("{}", tr(Msg::QueryNeedsCriterion));
+                return Err(failed(1));
+            }
+            let filter = criteria.into_filter(clock.today())?;
+            // asking for done or cancelled entries implies showing them
+            let show_all = cli.show_all || filter.status.is_some_and(Status::is_closed);
 
-            // If exact date provided, it overrides from/to
-            let (from_date, to_date) = if let Some(d) = date {
-                let parsed = NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok();
-                (parsed, parsed)
-            } else {
-                (
-                    from.and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()),
-                    to.and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()),
-                )
-            };
-
-            let by_date = filter_by_date_range(entries, from_date, to_date);
-            let by_tags = filter_by_tags(by_date, &tag, any);
+            // Use the sidecar index when one exists; otherwise read the whole file. Either way the
+            // filter is applied once per entry while printing.
+            let content;
+            let entries = match index::query(&file_path, &filter, read_only || is_remote())? {
+                Some(mut matches) => {
+                    matches.sort_by_key(list_order);
+                    matches
+                }
+                None => {
+                    content = read_file(&file_path)?;
+                    sorted_entries(&content, file_format)
+                }
+            };
             // Print incomplete matches first; if --show-all, show completed matches in a separate table
-            print_titled_tables(&by_tags, cli.show_all);
+            let mut out = String::new();
+            let shown = render_titled_tables(&mut out, entries.iter().filter(|e| filter.matches(e) && context.matches(e)), show_all, cli.porcelain);
+            output::emit(&out, cli.copy, paging)?;
+            if shown == 0 {
+                return Err(failed(EXIT_NO_MATCH));
             }
+        }
+        Some(Commands::Count { criteria, breakdown }) => {
+            let filter = criteria.into_filter(clock.today())?;
+            let show_all = cli.show_all || filter.status.is_some_and(|st| st.is_closed() || st == Status::Someday);
+            // counting needs no ordering, so stream the file instead of loading and sorting it
+            let matching = entries_iter(&file_path)?.filter(|e| filter.matches(e) && context.matches(e));
+            print_count(matching, &clock, breakdown, show_all);
+        }
         Some(Commands::Add { line }) => {
             // Validate and normalize the line before appending
-            let parsed = match parse_line(&line) {
+            let mut parsed = match parse_input_line(&line) {
                 Some(e) => e,
                 None => {
-                    eprintln!("Provided line is malformed; expected: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2");
-                    std::process::exit(1);
+                    eprintln!("{}", tr(Msg::MalformedLine));
+                    return Err(failed(1));
                 }
             };
-            let norm = entry_to_line(&parsed);
-            append_entry(&file_path, &norm)?;
-            println!("Appended normalized entry to {}", file_path.display());
+            if let Some(name) = config.author()
+                && added_by(&parsed).is_none()
+            {
+                set_tag_value(&mut parsed, "by", &name);
+            }
+            if config.sorted_writes {
+                write_entry_sorted(&file_path, &parsed)?;
+                println!("{}", trf(Msg::InsertedEntry, &[&file_path.display()]));
+            } else {
+                let norm = entry_to_line(&parsed, file_format);
+                append_entry(&file_path, &norm)?;
+                println!("{}", trf(Msg::AppendedEntry, &[&file_path.display()]));
+            }
+            warn_if_day_over_capacity(&file_path, parsed.date, &config)?;
+        }
+        Some(Commands::Edit { where_: true, criteria, replace, yes, .. }) => {
+            if criteria.is_empty() {
+                eprintln!("{}", tr(Msg::WhereNeedsCriterion));
+                return Err(failed(1));
+            }
+            let replace = replace.unwrap_or_default();
+            let Some((from, to)) = parse_substitution(&replace) else {
+                eprintln!("{}", trf(Msg::MalformedSubstitution, &[&replace]));
+                return Err(failed(1));
+            };
+            let filter = criteria.into_filter(clock.today())?;
+            let show_all = cli.show_all || filter.status.is_some_and(Status::is_closed);
+
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let mut changes = Vec::new();
+            for i in listed_indices(&entries, show_all, &context) {
+                let e = &entries[i];
+                if !filter.matches(e) || !e.desc.contains(from) {
+                    continue;
+                }
+                // a substitution that would leave no description is not applied
+                let desc = e.desc.replace(from, to).trim().to_string();
+                if desc.is_empty() {
+                    continue;
+                }
+                let date = dates::display(e.date);
+                println!("{}  - {}", date, e.desc);
+                println!("{}  + {}", " ".repeat(date.chars().count()), desc);
+                let edited = Entry { desc: desc.into(), ..e.clone() };
+                changes.push((e.line.expect("entries read from a file have line numbers"), Some(entry_to_line(&edited, file_format))));
+            }
+            if changes.is_empty() {
+                println!("{}", trf(Msg::NothingToReplace, &[&from]));
+                return Err(failed(EXIT_NO_MATCH));
             }
-        Some(Commands::Edit { index, line }) => {
+            if !yes {
+                print!("{}", trf(Msg::ApplyChanges, &[&changes.len()]));
+                io::Write::flush(&mut io::stdout())?;
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("{}", tr(Msg::NoChanges));
+                    return Ok(());
+                }
+            }
+            backup_before_bulk(&file_path, &clock)?;
+            change_lines(&file_path, &changes)?;
+            println!("{}", trf(Msg::EditedEntries, &[&changes.len(), &file_path.display()]));
+        }
+        Some(Commands::Edit { index, line, criteria, .. }) => {
+            if !criteria.is_empty() {
+                eprintln!("{}", tr(Msg::FiltersNeedWhere));
+                return Err(failed(1));
+            }
+            let (Some(index), Some(line)) = (index, line) else {
+                unreachable!("clap requires INDEX and LINE without --where")
+            };
             // Validate replacement
-            let parsed = match parse_line(&line) {
+            let parsed = match parse_input_line(&line) {
                 Some(e) => e,
                 None => {
-                    eprintln!("Replacement line is malformed; expected: YYYY-MM-DD<TAB>Description<TAB>tag1,tag2");
-                    std::process::exit(1);
+                    eprintln!("{}", tr(Msg::MalformedReplacement));
+                    return Err(failed(1));
                 }
             };
 
-
             // Map the user-provided index (1-based within visible list) to the original entries vector
-            let vis_idxs = visible_indices(&entries, cli.show_all);
-            if index == 0 || index > vis_idxs.len() {
-                eprintln!("Index out of range: {} (there are {} visible entries)", index, vis_idxs.len());
-                std::process::exit(1);
-            }
-            let orig_idx = vis_idxs[index - 1];
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
 
-            // Replace (mapped index)
-            entries[orig_idx] = parsed;
+            // Replace (mapped index), keeping the line the original came from
+            let replaced = Entry {
+                line: entries[orig_idx].line,
+                ..parsed
+            };
+
+            // Rewrite only that line; every other line of the file is left byte-for-byte as is.
+            // In sorted mode an entry whose date changed is moved to its new position instead.
+            if config.sorted_writes && replaced.date != entries[orig_idx].date {
+                write_entry_sorted(&file_path, &replaced)?;
+            } else {
+                rewrite_entry(&file_path, &replaced)?;
+            }
+            println!("{}", trf(Msg::ReplacedEntry, &[&index, &file_path.display()]));
+        }
+        Some(Commands::Retag { criteria, add, remove }) => {
+            if criteria.is_empty() {
+                eprintln!("{}", tr(Msg::RetagNeedsCriterion));
+                return Err(failed(1));
+            }
+            if let Some(bad) = add.iter().find(|t| t.trim().is_empty() || t.contains([',', '\t'])) {
+                eprintln!("{}", trf(Msg::InvalidTag, &[bad]));
+                return Err(failed(1));
+            }
+            let filter = criteria.into_filter(clock.today())?;
+            let show_all = cli.show_all || filter.status.is_some_and(Status::is_closed);
 
-            // Write all entries back to the file (normalized)
-            write_entries_to_file(&file_path, &entries)?;
-            println!("Replaced entry {} in {}", index, file_path.display());
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let mut changes = Vec::new();
+            for i in listed_indices(&entries, show_all, &context) {
+                if !filter.matches(&entries[i]) {
+                    continue;
+                }
+                let mut e = entries[i].clone();
+                e.tags.retain(|t| !remove.iter().any(|r| t.eq_ignore_ascii_case(r)));
+                for tag in &add {
+                    if !e.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                        e.tags.push(Arc::from(tag.trim()));
+                    }
+                }
+                if e.tags != entries[i].tags {
+                    changes.push((e.line.expect("entries read from a file have line numbers"), Some(entry_to_line(&e, file_format))));
+                }
+            }
+            if !changes.is_empty() {
+                backup_before_bulk(&file_path, &clock)?;
             }
+            change_lines(&file_path, &changes)?;
+            println!("{}", trf(Msg::RetaggedEntries, &[&changes.len(), &file_path.display()]));
+        }
+        Some(Commands::Move { index, tag, any, project, status, to }) => {
+            let filter = Filter { tags: tag, any, project, status, ..Filter::default() };
+            if index.is_none() && filter.tags.is_empty() && filter.project.is_none() && filter.status.is_none() {
+                eprintln!("{}", tr(Msg::MoveNeedsSelection));
+                return Err(failed(1));
+            }
+            if to.canonicalize().is_ok_and(|t| file_path.canonicalize().is_ok_and(|f| f == t)) {
+                eprintln!("{}", trf(Msg::MoveToSameFile, &[&to.display()]));
+                return Err(failed(1));
+            }
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let moved: Vec<&Entry> = match index {
+                Some(index) => vec![&entries[resolve_index(&entries, index, cli.show_all, &context)?]],
+                None => {
+                    let show_all = cli.show_all || filter.status.is_some_and(Status::is_closed);
+                    listed_indices(&entries, show_all, &context)
+                        .into_iter()
+                        .map(|i| &entries[i])
+                        .filter(|e| filter.matches(e))
+                        .collect()
+                }
+            };
+            if moved.is_empty() {
+                println!("{}", tr(Msg::NoMatchingOpen));
+                return Err(failed(EXIT_NO_MATCH));
+            }
+            if index.is_none() {
+                backup_before_bulk(&file_path, &clock)?;
+            }
+            move_entries(&file_path, &to, &moved, config.sorted_writes)?;
+            println!("{}", trf(Msg::MovedEntries, &[&moved.len(), &file_path.display(), &to.display()]));
+        }
         Some(Commands::Complete { index }) => {
             // Map index from visible list to original entries vector
-            let vis_idxs = visible_indices(&entries, cli.show_all);
-            if index == 0 || index > vis_idxs.len() {
-                eprintln!("Index out of range: {} (there are {} visible entries)", index, vis_idxs.len());
-                std::process::exit(1);
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
+
+            let next = change_status(&file_path, &mut entries[orig_idx], Status::Done, &clock, config.sorted_writes, config.author().as_deref())?;
+            println!("{}", trf(Msg::CompletedEntry, &[&index, &file_path.display()]));
+            if let Some(date) = next {
+                println!("{}", trf(Msg::NextOccurrence, &[&dates::display(date)]));
+            }
+        }
+        Some(Commands::Start { index, track }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
+            let e = &mut entries[orig_idx];
+            if track {
+                e.tags.retain(|t| !t.starts_with("started:"));
+                e.tags.push(Arc::from(format!("started:{}", clock.now.format(TIMESTAMP))));
             }
-            let orig_idx = vis_idxs[index - 1];
+            change_status(&file_path, e, Status::InProgress, &clock, config.sorted_writes, config.author().as_deref())?;
+            println!("{}", trf(Msg::StatusSet, &[&index, &Status::InProgress.name(), &file_path.display()]));
 
-            let tags = &mut entries[orig_idx].tags;
-            // add 'complete' tag if not already present (case-insensitive)
-            if !tags.iter().any(|t| t.eq_ignore_ascii_case("complete")) {
-                tags.push("complete".to_string());
+            let doing = entries.iter().filter(|e| status(e) == Status::InProgress).count();
+            if config.max_in_progress > 0 && doing > config.max_in_progress {
+                eprintln!("{}", trf(Msg::TooManyInProgress, &[&doing, &config.max_in_progress]));
+            }
+        }
+        Some(Commands::Status { index: None, format, tmux, .. }) => {
+            // called on every bar refresh, so stream the file rather than sorting it
+            let entries: Vec<Entry> = entries_iter(&file_path)?.filter(|e| context.matches(e)).collect();
+            let c = stats::counts(&entries, &clock);
+            if tmux {
+                let mut parts = Vec::new();
+                if c.overdue > 0 {
+                    parts.push(format!("#[fg=red]{}#[default]", trf(Msg::OverdueCount, &[&c.overdue])));
+                }
+                if c.due_today > 0 {
+                    parts.push(format!("#[fg=yellow]{}#[default]", trf(Msg::DueCount, &[&c.due_today])));
+                }
+                if !parts.is_empty() {
+                    println!("{}", parts.join(" "));
+                }
+                return Ok(());
+            }
+            let text = trf(Msg::StatusLine, &[&c.due_today, &c.overdue]);
+            match format {
+                BarFormat::Plain => println!("{}", text),
+                BarFormat::Json => println!("{}", stats::bar_json(&c, &text)),
+                BarFormat::Waybar => {
+                    let tooltip = trf(Msg::StatsOpen, &[&c.open, &c.overdue, &c.due_today, &c.undated]);
+                    println!("{}", stats::waybar_json(&c, &text, &tooltip));
+                }
+            }
+        }
+        Some(Commands::Status { index: Some(index), new_status, .. }) => {
+            let new_status = new_status.expect("clap requires a status with an index");
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
+            let next = change_status(&file_path, &mut entries[orig_idx], new_status, &clock, config.sorted_writes, config.author().as_deref())?;
+            println!("{}", trf(Msg::StatusSet, &[&index, &new_status.name(), &file_path.display()]));
+            if let Some(date) = next {
+                println!("{}", trf(Msg::NextOccurrence, &[&dates::display(date)]));
+            }
+        }
+        Some(Commands::Plan { days, per_day, hours, undated, yes }) => {
+            let today = clock.today();
+            let content = read_file(&file_path)?;
+            let entries = parse_entries(&content, file_format);
+            let open: Vec<&Entry> = entries.iter().filter(|e| is_active(e) && context.matches(e)).collect();
+            let horizon = today + chrono::Duration::days(days.into());
+            let configured = Capacity::from_config(&config);
+            let capacity = Capacity {
+                entries: per_day.map_or(if config.day_capacity > 0 { configured.entries } else { 5 }, |n| n as usize),
+                minutes: hours.map(|h| (h * 60.0) as u32).or(configured.minutes),
+            };
+            let (moves, load) = plan_schedule(&open, today, horizon, capacity, undated);
+            // what is left over capacity is made of entries plan may not move
+            for (date, &(count, minutes)) in load.range(today..horizon) {
+                capacity.warn_if_over(*date, count, minutes);
+            }
+            if moves.is_empty() {
+                println!("{}", tr(Msg::NothingToPlan));
+                return Ok(());
+            }
+            for (e, date) in &moves {
+                println!("{}  ->  {}  {}", dates::display(e.date), dates::display(*date), e.desc);
             }
+            if !yes {
+                print!("{}", trf(Msg::ApplyChanges, &[&moves.len()]));
+                io::Write::flush(&mut io::stdout())?;
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("{}", tr(Msg::NoChanges));
+                    return Ok(());
+                }
+            }
+            let changes: Vec<(usize, Option<String>)> = moves
+                .iter()
+                .map(|(e, date)| {
+                    let moved = Entry { date: *date, ..(*e).clone() };
+                    (e.line.expect("entries read from a file have line numbers"), Some(entry_to_line(&moved, file_format)))
+                })
+                .collect();
+            backup_before_bulk(&file_path, &clock)?;
+            change_lines(&file_path, &changes)?;
+            if config.sorted_writes {
+                sort_file(&file_path)?;
+            }
+            println!("{}", trf(Msg::Planned, &[&moves.len(), &file_path.display()]));
+        }
+        Some(Commands::Skip { index }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let e = &entries[resolve_index(&entries, index, cli.show_all, &context)?];
+            // the next occurrence counts from the skipped one, or from today if that is overdue
+            // or undated
+            let from = if e.date == dates::UNDATED { clock.today() } else { e.date.max(clock.today()) };
+            let Some(mut next) = next_after_completion(e, from) else {
+                eprintln!("{}", trf(Msg::NotRepeating, &[&index]));
+                return Err(failed(1));
+            };
+            next.line = e.line;
+            if config.sorted_writes {
+                write_entry_sorted(&file_path, &next)?;
+            } else {
+                rewrite_entry(&file_path, &next)?;
+            }
+            println!("{}", trf(Msg::SkippedOccurrence, &[&index, &dates::display(next.date)]));
+            warn_if_day_over_capacity(&file_path, next.date, &config)?;
+        }
+        Some(Commands::Reschedule { index, date }) => {
+            let new_date = (date.trim() == "-").then_some(dates::UNDATED).or_else(|| dates::parse_relative(&date, clock.today())).ok_or_else(|| {
+                eprintln!("{}", trf(Msg::InvalidDate, &[&date]));
+                failed(1)
+            })?;
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
+            let e = &mut entries[orig_idx];
+            e.date = new_date;
+            if config.sorted_writes {
+                write_entry_sorted(&file_path, e)?;
+            } else {
+                rewrite_entry(&file_path, e)?;
+            }
+            println!("{}", trf(Msg::Rescheduled, &[&index, &dates::display(new_date)]));
+            warn_if_day_over_capacity(&file_path, new_date, &config)?;
+        }
+        Some(Commands::Activate { index }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let parked: Vec<usize> = (0..entries.len())
+                .filter(|&i| status(&entries[i]) == Status::Someday && context.matches(&entries[i]))
+                .collect();
+            if index == 0 || index > parked.len() {
+                eprintln!("{}", trf(Msg::IndexOutOfRange, &[&index, &parked.len()]));
+                return Err(failed(1));
+            }
+            change_status(&file_path, &mut entries[parked[index - 1]], Status::Todo, &clock, config.sorted_writes, None)?;
+            println!("{}", trf(Msg::Activated, &[&entries[parked[index - 1]].desc, &file_path.display()]));
+        }
+        Some(Commands::Annotate { index, text }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let orig_idx = resolve_index(&entries, index, cli.show_all, &context)?;
+            let e = &mut entries[orig_idx];
+            e.tags.push(Arc::from(annotation_tag(&clock.now.format(TIMESTAMP).to_string(), &text)));
+            rewrite_entry(&file_path, e)?;
+            println!("{}", trf(Msg::Annotated, &[&index, &file_path.display()]));
+        }
+        Some(Commands::Show { index }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let e = &entries[resolve_index(&entries, index, cli.show_all, &context)?];
+            print!("{}", entry_detail(e, &file_path));
+        }
+        Some(Commands::Menu { action }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            let listed = listed_indices(&entries, cli.show_all, &context);
+            let menu_line = |e: &Entry<'_>| format!("{}  {}", dates::display(e.date), e.desc);
+            let Some(action) = action else {
+                let mut out = String::new();
+                for &i in &listed {
+                    writeln!(out, "{}", menu_line(&entries[i])).ok();
+                }
+                print!("{}", out);
+                return Ok(());
+            };
+            let mut selected = String::new();
+            io::stdin().read_line(&mut selected)?;
+            let selected = selected.trim_end_matches(['\r', '\n']);
+            // the launcher was dismissed
+            if selected.is_empty() {
+                return Ok(());
+            }
+            let Some(pos) = listed.iter().position(|&i| menu_line(&entries[i]) == selected) else {
+                eprintln!("{}", trf(Msg::NoMenuMatch, &[&selected]));
+                return Err(failed(1));
+            };
+            let (index, orig_idx) = (pos + 1, listed[pos]);
+            match action {
+                MenuAction::Complete => {
+                    let next = change_status(&file_path, &mut entries[orig_idx], Status::Done, &clock, config.sorted_writes, config.author().as_deref())?;
+                    println!("{}", trf(Msg::CompletedEntry, &[&index, &file_path.display()]));
+                    if let Some(date) = next {
+                        println!("{}", trf(Msg::NextOccurrence, &[&dates::display(date)]));
+                    }
+                }
+                MenuAction::Open => {
+                    let Some(link) = links(&entries[orig_idx]).first().copied() else {
+                        eprintln!("{}", tr(Msg::NoLinks));
+                        return Err(failed(1));
+                    };
+                    if let Err(err) = output::open_external(link) {
+                        eprintln!("Could not open {}: {}", link, err);
+                        return Err(failed(1));
+                    }
+                }
+                MenuAction::Edit => {
+                    let line = entries[orig_idx].line.map_or(1, |n| n + 1);
+                    if let Err(err) = output::open_in_editor(&file_path, line) {
+                        eprintln!("Could not open {}: {}", file_path.display(), err);
+                        return Err(failed(1));
+                    }
+                }
+            }
+        }
+        Some(Commands::Open { index, link }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let e = &entries[resolve_index(&entries, index, cli.show_all, &context)?];
+            let found = links(e);
+            if found.is_empty() {
+                eprintln!("{}", tr(Msg::NoLinks));
+                return Err(failed(1));
+            }
+            let choice = match link {
+                Some(n) => n,
+                None if found.len() == 1 => 1,
+                None => {
+                    for (i, l) in found.iter().enumerate() {
+                        println!("{:>3}. {}", i + 1, l);
+                    }
+                    print!("{}", trf(Msg::WhichLink, &[&found.len()]));
+                    io::Write::flush(&mut io::stdout())?;
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input)?;
+                    if input.trim().is_empty() {
+                        return Ok(());
+                    }
+                    input.trim().parse().unwrap_or(0)
+                }
+            };
+            if choice == 0 || choice > found.len() {
+                eprintln!("{}", trf(Msg::NoSuchLink, &[&choice, &found.len()]));
+                return Err(failed(1));
+            }
+            if let Err(err) = output::open_external(found[choice - 1]) {
+                eprintln!("Could not open {}: {}", found[choice - 1], err);
+                return Err(failed(1));
+            }
+        }
+        Some(Commands::Share { index, qr }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let e = &entries[resolve_index(&entries, index, cli.show_all, &context)?];
 
-            write_entries_to_file(&file_path, &entries)?;
-            println!("Marked entry {} as complete in {}", index, file_path.display());
+            let mut text = format!("{}\n{}", dates::display(e.date), e.desc);
+            if !e.tags.is_empty() {
+                text.push_str(&format!("\n{}", e.tags.join(",")));
+            }
+            if qr {
+                match output::render_qr(&text) {
+                    Ok(code) => print!("{}", code),
+                    Err(err) => {
+                        eprintln!("Could not render QR code: {}", err);
+                        return Err(failed(1));
+                    }
+                }
+            } else {
+                text.push('\n');
+                output::emit(&text, cli.copy, false)?;
+            }
+        }
+        Some(Commands::Export { format, output }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = sorted_entries(&content, file_format);
+            entries.retain(|e| context.matches(e));
+            let rendered = match format {
+                ExportFormat::Html => export::daily_sheet_html(&entries, &clock),
+                ExportFormat::Json => export::entries_json(&entries),
+                ExportFormat::Ics => export::entries_ics(&entries, clock.now),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, rendered)?;
+                    println!("{}", trf(Msg::ExportedTo, &[&path.display()]));
+                }
+                None => print!("{}", rendered),
+            }
+        }
+        Some(Commands::Import { path, url, merge }) => {
+            let (source, format) = match (&path, &url) {
+                (Some(path), _) => (read_file(path)?, import::Format::IronList),
+                (None, Some(url)) => {
+                    let (body, content_type) = import::fetch(url).map_err(|err| {
+                        eprintln!("{}", trf(Msg::DownloadFailed, &[url, &err]));
+                        failed(1)
+                    })?;
+                    let format = import::Format::detect(&body, content_type.as_deref());
+                    (body, format)
+                }
+                (None, None) => unreachable!("clap requires FILE or --url"),
+            };
+            let incoming = match format {
+                import::Format::IronList => parse_entries(&source, path.as_deref().map_or(FileFormat::Tsv, FileFormat::of)),
+                import::Format::TodoTxt => import::parse_todo_txt(&source),
+                import::Format::ICalendar => import::parse_ics(&source),
+            };
+            if let Some(url) = &url
+                && incoming.is_empty()
+            {
+                eprintln!("{}", trf(Msg::NothingToImport, &[url]));
+                return Err(failed(1));
+            }
+            if merge || url.is_some() {
+                let content = read_file(&file_path)?;
+                let (added, report) = import::merge(&parse_entries(&content, file_format), incoming);
+                if !added.is_empty() {
+                    backup_before_bulk(&file_path, &clock)?;
+                }
+                add_entries(&file_path, &added.iter().collect::<Vec<_>>(), config.sorted_writes)?;
+                println!(
+                    "{}",
+                    trf(Msg::ImportMerged, &[&report.added, &report.skipped, &report.conflicts, &file_path.display()])
+                );
+            } else {
+                add_entries(&file_path, &incoming.iter().collect::<Vec<_>>(), config.sorted_writes)?;
+                println!("{}", trf(Msg::ImportedEntries, &[&incoming.len(), &file_path.display()]));
+            }
+        }
+        Some(Commands::Next {}) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            match next_pick(&entries, &context) {
+                Some((pos, number)) => {
+                    let mut out = String::new();
+                    if cli.porcelain {
+                        render_porcelain(&mut out, &[&entries[pos]]);
+                    } else {
+                        render_numbered_from(&mut out, &[&entries[pos]], number);
+                    }
+                    output::emit(&out, cli.copy, false)?;
+                }
+                None => println!("{}", tr(Msg::NothingToDo)),
+            }
+        }
+        Some(Commands::Random { tag, any }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let filter = Filter {
+                tags: tag,
+                any,
+                ..Filter::default()
+            };
+            match random_pick(&entries, &context, &filter) {
+                Some((pos, number)) => {
+                    let mut out = String::new();
+                    if cli.porcelain {
+                        render_porcelain(&mut out, &[&entries[pos]]);
+                    } else {
+                        render_numbered_from(&mut out, &[&entries[pos]], number);
+                    }
+                    output::emit(&out, cli.copy, false)?;
+                }
+                None => println!("{}", tr(Msg::NoMatchingOpen)),
             }
+        }
+        Some(Commands::Board { columns, limit }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let shown: Vec<&Entry<'_>> = entries.iter().filter(|e| context.matches(e)).collect();
+            // a column named after a status holds entries with that status, any other name is a tag
+            let in_column = |e: &Entry<'_>, name: &str| match Status::parse(name) {
+                Some(st) => status(e) == st,
+                None => e.tags.iter().any(|t| t.eq_ignore_ascii_case(name)),
+            };
+            let mut board: Vec<board::Column<'_, '_>> = if columns.is_empty() {
+                let by_status = |wanted: &[Status]| -> Vec<&Entry<'_>> {
+                    shown.iter().copied().filter(|e| wanted.contains(&status(e))).collect()
+                };
+                let backlog = by_status(&[Status::Todo, Status::Waiting]);
+                let doing = by_status(&[Status::InProgress]);
+                let done = by_status(&[Status::Done]);
+                vec![
+                    board::Column { title: tr(Msg::Backlog).to_string(), entries: backlog },
+                    board::Column { title: tr(Msg::InProgress).to_string(), entries: doing },
+                    board::Column { title: tr(Msg::Done).to_string(), entries: done },
+                ]
+            } else {
+                columns
+                    .iter()
+                    .map(|tag| board::Column {
+                        title: tag.clone(),
+                        entries: shown.iter().copied().filter(|e| in_column(e, tag)).collect(),
+                    })
+                    .collect()
+            };
+            let last = board.len() - 1;
+            for (i, column) in board.iter_mut().enumerate() {
+                // counts cover every entry in the column, also those cut off by --limit
+                column.title = format!("{} ({})", column.title, column.entries.len());
+                if let Some(n) = limit {
+                    // the default Done column keeps its most recent entries, the others the earliest
+                    if i == last && columns.is_empty() {
+                        let skip = column.entries.len().saturating_sub(n);
+                        column.entries.drain(..skip);
+                    } else {
+                        column.entries.truncate(n);
+                    }
+                }
+            }
+            let out = board::render(&board, output::terminal_width());
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Projects {}) => {
+            // keyed case-insensitively, shown with the spelling seen first; no project sorts last
+            let mut stats: BTreeMap<Option<String>, (String, [usize; 3])> = BTreeMap::new();
+            for e in entries_iter(&file_path)?.filter(|e| context.matches(e)) {
+                let name = project(&e).unwrap_or("");
+                let key = (!name.is_empty()).then(|| name.to_lowercase());
+                let (_, [open, overdue, done]) = stats.entry(key).or_insert_with(|| (name.to_string(), [0; 3]));
+                if is_complete(&e) {
+                    *done += 1;
+                } else {
+                    *open += 1;
+                    if e.date < clock.today_for(&e) {
+                        *overdue += 1;
+                    }
+                }
+            }
+            let mut out = String::new();
+            let headers = [tr(Msg::ColOpen), tr(Msg::Overdue), tr(Msg::ColDone)];
+            writeln!(out, "{:20}  {:>10}  {:>10}  {:>10}", tr(Msg::ColProject), headers[0], headers[1], headers[2]).ok();
+            writeln!(out, "{:-<20}  {:->10}  {:->10}  {:->10}", "", "", "", "").ok();
+            // BTreeMap puts None first; list named projects before the unassigned entries
+            let (unassigned, named): (Vec<_>, Vec<_>) = stats.into_iter().partition(|(k, _)| k.is_none());
+            for (key, (name, [open, overdue, done])) in named.into_iter().chain(unassigned) {
+                let name = if key.is_some() { name } else { tr(Msg::NoProject).to_string() };
+                writeln!(out, "{:20}  {:>10}  {:>10}  {:>10}", name, open, overdue, done).ok();
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Stats { velocity, json }) => {
+            let content = read_file(&file_path)?;
+            let mut entries = parse_entries(&content, file_format);
+            entries.retain(|e| context.matches(e));
+            let c = stats::counts(&entries, &clock);
+            let tags = stats::by_tag(&entries);
+            let aging = stats::overdue_aging(&entries, &clock);
+            let v = stats::velocity(&entries, clock.today());
+            if json {
+                output::emit(&stats::to_json(clock.today(), &c, &tags, &aging, &v), cli.copy, false)?;
+                return Ok(());
+            }
+            let mut out = String::new();
+            writeln!(out, "{}", trf(Msg::StatsOpen, &[&c.open, &c.overdue, &c.due_today, &c.undated])).ok();
+            writeln!(out, "{}", trf(Msg::StatsClosed, &[&c.someday, &c.done, &c.cancelled])).ok();
+            if c.overdue > 0 {
+                writeln!(out, "{}", trf(Msg::OverdueAging, &[&aging.days_1_7, &aging.days_8_30, &aging.days_31_90, &aging.over_90])).ok();
+            }
+            if !tags.is_empty() {
+                writeln!(out, "\n{:20}  {:>6}  {:>6}", tr(Msg::ColTags), tr(Msg::ColOpen), tr(Msg::ColDone)).ok();
+                for t in &tags {
+                    writeln!(out, "{:20}  {:>6}  {:>6}", t.tag, t.open, t.done).ok();
+                }
+            }
+            if velocity {
+                let bars = |out: &mut String, heading: Msg, periods: &[stats::Period], label: &dyn Fn(NaiveDate) -> String| {
+                    writeln!(out, "\n{}", tr(heading)).ok();
+                    for p in periods {
+                        let line = format!("  {:<10}  {:>3}  {}", label(p.start), p.done, "#".repeat(p.done));
+                        writeln!(out, "{}", line.trim_end()).ok();
+                    }
+                };
+                bars(&mut out, Msg::DonePerDay, &v.days, &|d| dates::display(d));
+                bars(&mut out, Msg::DonePerWeek, &v.weeks, &|d| d.format("%G-W%V").to_string());
+                bars(&mut out, Msg::DonePerMonth, &v.months, &|d| d.format("%Y-%m").to_string());
+                writeln!(out).ok();
+                writeln!(out, "{}", trf(Msg::DoneAverage, &[&format!("{:.1}", v.avg_7), &format!("{:.1}", v.avg_28)])).ok();
+                writeln!(out, "{}", trf(Msg::ScheduledAverage, &[&format!("{:.1}", v.scheduled_7)])).ok();
+                if c.done > 0 && !entries.iter().any(|e| completed_on(e).is_some()) {
+                    writeln!(out, "{}", tr(Msg::NoCompletionDates)).ok();
+                }
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Prompt { min, days }) => {
+            let (min, days) = (min.unwrap_or(config.prompt_threshold), days.unwrap_or(config.prompt_days));
+            // a prompt must stay quiet and fast: stream the file and print nothing if it can't be read
+            let Ok(entries) = entries_iter(&file_path) else {
+                return Ok(());
+            };
+            let due = entries
+                .filter(|e| is_active(e) && e.date != dates::UNDATED && context.matches(e))
+                .filter(|e| (e.date - clock.today_for(e)).num_days() <= days.into())
+                .count();
+            if due > 0 && due >= min {
+                println!("⚑{}", due);
+            }
+        }
+        Some(Commands::Serve { listen, token, .. }) => {
+            let listener = std::net::TcpListener::bind(&listen).map_err(|err| {
+                eprintln!("Could not listen on {}: {}", listen, err);
+                failed(1)
+            })?;
+            let addr = listener.local_addr()?;
+            let query = token.as_ref().map(|t| format!("?token={}", t)).unwrap_or_default();
+            let url = format!("{}{}{}", addr, serve::FEED_PATH, query);
+            println!("{}", trf(Msg::ServingFeed, &[&format!("http://{}", url), &format!("webcal://{}", url)]));
+            // read for every request, so subscribers always get the current list
+            serve::run(listener, token.as_deref(), || {
+                let content = read_file(&file_path)?;
+                let mut entries = parse_entries(&content, file_format);
+                entries.retain(|e| context.matches(e));
+                Ok(export::entries_ics(&entries, Clock::new(config.timezone).now))
+            });
+        }
+        Some(Commands::Stale { days }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let (written, log_start) = audit::last_written(&file_path)?;
+            let today = clock.today();
+            // an entry the log never saw being written has been there since before it started
+            let mut stale: Vec<(Option<NaiveDate>, &Entry)> = entries
+                .iter()
+                .filter(|e| is_active(e) && context.matches(e))
+                .map(|e| (written.get(e.raw_line.as_ref()).copied().or(log_start), e))
+                .filter(|(touched, _)| touched.is_none_or(|d| (today - d).num_days() > days.into()))
+                .collect();
+            if stale.is_empty() {
+                println!("{}", trf(Msg::NothingStale, &[&days]));
+                return Ok(());
+            }
+            stale.sort_by_key(|(touched, _)| *touched);
+            let mut out = String::new();
+            writeln!(out, "{:>3}  {:10}  {:>5}  {}", tr(Msg::ColNo), tr(Msg::ColTouched), tr(Msg::ColDays), tr(Msg::ColTask)).ok();
+            writeln!(out, "{:->3}  {:->10}  {:->5}  {:->30}", "", "", "", "").ok();
+            for (i, (touched, e)) in stale.iter().enumerate() {
+                let (since, age) = match touched {
+                    Some(d) => (dates::display(*d), (today - *d).num_days().to_string()),
+                    None => ("?".to_string(), "?".to_string()),
+                };
+                writeln!(out, "{:>3}. {:10}  {:>5}  {}", i + 1, since, age, e.desc).ok();
+            }
+            if log_start.is_none() {
+                writeln!(out, "\n{}", tr(Msg::NoChangeHistory)).ok();
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::People {}) => {
+            // keyed case-insensitively, shown with the spelling seen first; nobody sorts last
+            let mut stats: BTreeMap<Option<String>, (String, [usize; 2])> = BTreeMap::new();
+            let mut count = |name: Option<&str>, slot: usize| {
+                let key = name.map(str::to_lowercase);
+                stats.entry(key).or_insert_with(|| (name.unwrap_or("").to_string(), [0; 2])).1[slot] += 1;
+            };
+            for e in entries_iter(&file_path)?.filter(|e| context.matches(e)) {
+                if is_complete(&e) {
+                    count(done_by(&e), 1);
+                } else {
+                    count(added_by(&e), 0);
+                }
+            }
+            let mut out = String::new();
+            writeln!(out, "{:20}  {:>10}  {:>10}", tr(Msg::ColPerson), tr(Msg::ColOpen), tr(Msg::ColDone)).ok();
+            writeln!(out, "{:-<20}  {:->10}  {:->10}", "", "", "").ok();
+            let (nobody, named): (Vec<_>, Vec<_>) = stats.into_iter().partition(|(k, _)| k.is_none());
+            for (key, (name, [open, done])) in named.into_iter().chain(nobody) {
+                let name = if key.is_some() { name } else { tr(Msg::Unattributed).to_string() };
+                writeln!(out, "{:20}  {:>10}  {:>10}", name, open, done).ok();
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::History { action: None, limit }) => {
+            let records = audit::read(&file_path)?;
+            let mut out = String::new();
+            for r in records.iter().rev().take(limit.unwrap_or(usize::MAX)) {
+                writeln!(out, "{}  {}  {}", r.time, r.who, r.command).ok();
+                write_diff(&mut out, &r.removed, &r.added);
+            }
+            if out.is_empty() {
+                println!("{}", trf(Msg::NoHistory, &[&file_path.display()]));
+            } else {
+                output::emit(&out, cli.copy, paging)?;
+            }
+        }
+        Some(Commands::History { action: Some(HistoryAction::Show { number: None }), .. }) => {
+            let snapshots = audit::snapshots(&file_path)?;
+            if snapshots.is_empty() {
+                println!("{}", trf(Msg::NoSnapshots, &[&file_path.display()]));
+                return Ok(());
+            }
+            let mut out = String::new();
+            writeln!(out, "{:>6}  {:20}  {:>8}", tr(Msg::ColNo), tr(Msg::ColTime), tr(Msg::ColEntries)).ok();
+            writeln!(out, "{:->6}  {:-<20}  {:->8}", "", "", "").ok();
+            for s in snapshots.iter().rev() {
+                let entries = std::fs::read_to_string(&s.path).map_or(0, |c| parse_entries(&c, file_format).len());
+                writeln!(out, "{:>6}  {:20}  {:>8}", s.number, s.time, entries).ok();
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::History { action: Some(HistoryAction::Show { number: Some(n) }), .. }) => {
+            output::emit(&load_snapshot(&file_path, n)?, cli.copy, paging)?;
+        }
+        Some(Commands::History { action: Some(HistoryAction::Diff { a, b }), .. }) => {
+            let old = load_snapshot(&file_path, a)?;
+            let new = match b {
+                Some(b) => load_snapshot(&file_path, b)?,
+                None => read_file(&file_path)?,
+            };
+            let (removed, added) = audit::diff(&old, &new);
+            let mut out = String::new();
+            write_diff(&mut out, &removed, &added);
+            if out.is_empty() {
+                println!("{}", tr(Msg::NoDifferences));
+            } else {
+                output::emit(&out, cli.copy, paging)?;
+            }
+        }
+        Some(Commands::History { action: Some(HistoryAction::Restore { number }), .. }) => {
+            let content = load_snapshot(&file_path, number)?;
+            backup_before_bulk(&file_path, &clock)?;
+            replace_file(&file_path, &content)?;
+            println!("{}", trf(Msg::RestoredSnapshot, &[&number, &file_path.display()]));
+        }
+        Some(Commands::Prune { completed_before, older_than, delete }) => {
+            let today = clock.today();
+            let cutoff = match (completed_before, older_than) {
+                (Some(date), _) => dates::parse_relative(&date, today).ok_or_else(|| {
+                    eprintln!("{}", trf(Msg::InvalidDate, &[&date]));
+                    failed(1)
+                })?,
+                (None, Some(age)) => dates::ago(&age, today).ok_or_else(|| {
+                    eprintln!("{}", trf(Msg::InvalidAge, &[&age]));
+                    failed(1)
+                })?,
+                (None, None) => unreachable!("clap requires a cutoff"),
+            };
+            let content = read_file(&file_path)?;
+            let entries = parse_entries(&content, file_format);
+            let pruned: Vec<&Entry> = entries.iter().filter(|e| is_complete(e) && finished_on(e) < cutoff).collect();
+            if pruned.is_empty() {
+                println!("{}", trf(Msg::PrunedEntries, &[&0, &file_path.display()]));
+                return Ok(());
+            }
+            backup_before_bulk(&file_path, &clock)?;
+            let target = if delete {
+                append_to_trash(&file_path, &pruned, today)?;
+                let removals: Vec<(usize, Option<String>)> = pruned.iter().filter_map(|e| e.line).map(|n| (n, None)).collect();
+                change_lines(&file_path, &removals)?;
+                trash_path(&file_path)
+            } else {
+                move_entries(&file_path, &archive_path(&file_path), &pruned, false)?;
+                archive_path(&file_path)
+            };
+            println!("{}", trf(Msg::PrunedEntries, &[&pruned.len(), &target.display()]));
+        }
+        Some(Commands::In { text }) => {
+            let thought = text.join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+            let inbox = inbox_path(&file_path);
+            append_entry(&inbox, &format!("{}\t{}", dates::store(dates::UNDATED), thought))?;
+            println!("{}", trf(Msg::Captured, &[&inbox.display()]));
+        }
+        Some(Commands::Inbox { action }) => {
+            let inbox = inbox_path(&file_path);
+            let content = match read_file(&inbox) {
+                Ok(content) => content,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(err) => return Err(err),
+            };
+            let items = parse_entries(&content, FileFormat::of(&inbox));
+            let pick = |index: usize| {
+                if index == 0 || index > items.len() {
+                    eprintln!("{}", trf(Msg::IndexOutOfRange, &[&index, &items.len()]));
+                    return Err(failed(1));
+                }
+                Ok(&items[index - 1])
+            };
+            match action {
+                None if items.is_empty() => println!("{}", tr(Msg::InboxEmpty)),
+                None => {
+                    let mut out = String::new();
+                    let shown: Vec<&Entry> = items.iter().collect();
+                    render_numbered(&mut out, &shown);
+                    output::emit(&out, cli.copy, paging)?;
+                }
+                Some(InboxAction::File { index, date, tags }) => {
+                    let item = pick(index)?;
+                    let date = match date {
+                        Some(date) => dates::parse_relative(&date, clock.today()).ok_or_else(|| {
+                            eprintln!("{}", trf(Msg::InvalidDate, &[&date]));
+                            failed(1)
+                        })?,
+                        None => item.date,
+                    };
+                    let mut task = Entry { date, line: None, ..item.clone() };
+                    task.tags.extend(tags.iter().map(|t| Arc::from(t.trim())));
+                    if let Some(name) = config.author() {
+                        set_tag_value(&mut task, "by", &name);
+                    }
+                    if config.sorted_writes {
+                        write_entry_sorted(&file_path, &task)?;
+                    } else {
+                        append_entry(&file_path, &entry_to_line(&task, file_format))?;
+                    }
+                    change_lines(&inbox, &[(item.line.expect("entries read from a file have line numbers"), None)])?;
+                    println!("{}", trf(Msg::Filed, &[&item.desc, &file_path.display()]));
+                }
+                Some(InboxAction::Drop { index }) => {
+                    let item = pick(index)?;
+                    change_lines(&inbox, &[(item.line.expect("entries read from a file have line numbers"), None)])?;
+                    println!("{}", trf(Msg::Dropped, &[&item.desc]));
+                }
+            }
+        }
+        Some(Commands::Trash { action: TrashAction::Purge { older_than } }) => {
+            let cutoff = older_than
+                .map(|age| {
+                    dates::ago(&age, clock.today()).ok_or_else(|| {
+                        eprintln!("{}", trf(Msg::InvalidAge, &[&age]));
+                        failed(1)
+                    })
+                })
+                .transpose()?;
+            let purged = purge_trash(&file_path, cutoff)?;
+            println!("{}", trf(Msg::PurgedTrash, &[&purged, &trash_path(&file_path).display()]));
+        }
+        Some(Commands::Snapshot {}) => {
+            match audit::take_snapshot(&file_path, &read_file(&file_path)?, clock.now)? {
+                Some(n) => println!("{}", trf(Msg::SnapshotTaken, &[&n, &file_path.display()])),
+                None => println!("{}", tr(Msg::SnapshotUnchanged)),
+            }
+            audit::prune_snapshots(&file_path, config.snapshot_keep.max(1))?;
+        }
+        Some(Commands::Review { action: Some(ReviewAction::Due {}) }) => {
+            let content = read_file(&file_path)?;
+            let entries = parse_entries(&content, file_format);
+            let today = clock.today();
+            let mut due: Vec<(NaiveDate, &Entry)> = entries
+                .iter()
+                .filter(|e| !is_complete(e) && context.matches(e))
+                .filter_map(|e| review_date(e).filter(|&d| d <= today).map(|d| (d, e)))
+                .collect();
+            if due.is_empty() {
+                println!("{}", tr(Msg::NothingDueForReview));
+                return Ok(());
+            }
+            due.sort_by_key(|(d, _)| *d);
+            let shown: Vec<&Entry> = due.into_iter().map(|(_, e)| e).collect();
+            let mut out = String::new();
+            if cli.porcelain {
+                render_porcelain(&mut out, &shown);
+            } else {
+                render_numbered(&mut out, &shown);
+            }
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Review { action: None }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            review::run(&file_path, &entries, &clock, config.sorted_writes, config.author().as_deref())?;
+        }
+        Some(Commands::Roll { mark }) => {
+            let moved = roll(&file_path, &clock, mark || config.roll_mark, config.sorted_writes)?;
+            println!("{}", trf(Msg::RolledEntries, &[&moved]));
+        }
+        Some(Commands::Sort {}) => {
+            if file_format == FileFormat::Markdown {
+                eprintln!("{}", tr(Msg::MarkdownUnsorted));
+                return Err(failed(1));
+            }
+            sort_file(&file_path)?;
+            println!("{}", trf(Msg::SortedFile, &[&file_path.display()]));
+        }
+        Some(Commands::Split { out_dir, .. }) => {
+            let content = read_file(&file_path)?;
+            let entries = sorted_entries(&content, file_format);
+            let mut groups: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+            for e in &entries {
+                let name = top_level_tag(e).map_or_else(|| "untagged".to_string(), split_file_stem);
+                groups.entry(name).or_default().push(e);
+            }
+            let targets: Vec<PathBuf> = groups.keys().map(|name| out_dir.join(format!("{}.txt", name))).collect();
+            if let Some(existing) = targets.iter().find(|t| t.exists()) {
+                eprintln!("{}", trf(Msg::SplitTargetExists, &[&existing.display()]));
+                return Err(failed(1));
+            }
+            std::fs::create_dir_all(&out_dir)?;
+            for (target, group) in targets.iter().zip(groups.values_mut()) {
+                group.sort_by_key(|e| e.date);
+                let mut text = String::new();
+                for e in group {
+                    text.push_str(&entry_to_line(e, FileFormat::Tsv));
+                    text.push('\n');
+                }
+                std::fs::write(target, text)?;
+            }
+            println!("{}", trf(Msg::SplitFile, &[&entries.len(), &groups.len(), &out_dir.display()]));
+        }
+        Some(Commands::Config { .. } | Commands::Context { .. } | Commands::Diff { .. }) => {
+            unreachable!("handled before the data file is resolved")
+        }
+        Some(Commands::Index { action: IndexAction::Rebuild {} }) => {
+            let count = index::rebuild(&file_path)?;
+            println!("{}", trf(Msg::IndexedEntries, &[&count, &index::index_path(&file_path).display()]));
+        }
+        Some(Commands::Index { action: IndexAction::Remove {} }) => {
+            if index::remove(&file_path)? {
+                println!("{}", trf(Msg::RemovedIndex, &[&index::index_path(&file_path).display()]));
+            } else {
+                println!("{}", trf(Msg::NoIndex, &[&file_path.display()]));
+            }
+        }
+    }
+
+    if let Some(before) = before {
+        finish_change(&file_path, &before, &config, &clock, &who)?;
     }
+    Ok(())
+}
 
+/// After a command changed `path` (which held `before`): move long-completed entries to the
+/// archive when `auto_archive_days` is set, then record the change.
+fn finish_change(path: &Path, before: &str, config: &Config, clock: &Clock, who: &str) -> io::Result<()> {
+    let current = || read_file(path).unwrap_or_default();
+    // Keep the working file tidy: a change moves long-completed entries to the archive.
+    if config.auto_archive_days > 0 && !is_remote() && current() != before {
+        let cutoff = clock.today() - chrono::Duration::days(config.auto_archive_days.into());
+        let content = read_file(path)?;
+        let entries = parse_entries(&content, FileFormat::of(path));
+        let old: Vec<&Entry> = entries.iter().filter(|e| is_complete(e) && finished_on(e) < cutoff).collect();
+        if !old.is_empty() {
+            move_entries(path, &archive_path(path), &old, false)?;
+        }
+    }
+    let command_line: Vec<String> = std::env::args().skip(1).collect();
+    record_change(path, before, &current(), clock, who, &command_line.join(" "), config.snapshot_keep);
     Ok(())
 }
 
+/// Print how many of `entries` are open (or, with `show_all`, how many there are), or with
+/// `breakdown` how many are open, overdue, due today, parked as someday and complete. Someday
+/// entries aren't open, as `list` leaves them out too.
+fn print_count<'a>(entries: impl IntoIterator<Item = impl Borrow<Entry<'a>>>, clock: &Clock, breakdown: bool, show_all: bool) {
+    let (mut open, mut overdue, mut due_today, mut someday, mut complete) = (0usize, 0usize, 0usize, 0usize, 0usize);
+    for e in entries {
+        let e = e.borrow();
+        if is_complete(e) {
+            complete += 1;
+        } else if status(e) == Status::Someday {
+            someday += 1;
+        } else {
+            open += 1;
+            let today = clock.today_for(e);
+            if e.date < today {
+                overdue += 1;
+            } else if e.date == today {
+                due_today += 1;
+            }
+        }
+    }
+    if breakdown {
+        println!("open={} overdue={} today={} someday={} complete={}", open, overdue, due_today, someday, complete);
+    } else if show_all {
+        println!("{}", open + someday + complete);
+    } else {
+        println!("{}", open);
+    }
+}
+
+/// The data file `first`, the files it includes, then each of `more` with the files it includes,
+/// every file once: the files `list` shows together.
+fn merged_view(first: &Path, more: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut view: Vec<PathBuf> = Vec::new();
+    for f in std::iter::once(first).chain(more.iter().map(PathBuf::as_path)) {
+        for path in std::iter::once(f.to_path_buf()).chain(included_files(f)?) {
+            if !view.contains(&path) {
+                view.push(path);
+            }
+        }
+    }
+    Ok(view)
+}
+
+/// The entries of `files` (read into `contents`) sorted together as `list` shows them, and for
+/// each the position in `files` of the file it comes from.
+fn merge_entries<'a>(files: &[PathBuf], contents: &'a [String]) -> (Vec<usize>, Vec<Entry<'a>>) {
+    let mut merged: Vec<(usize, Entry)> = contents
+        .iter()
+        .enumerate()
+        .flat_map(|(i, content)| parse_entries(content, FileFormat::of(&files[i])).into_iter().map(move |e| (i, e)))
+        .collect();
+    // stable, so entries of the first file come first within a date
+    merged.sort_by_key(|(_, e)| list_order(e));
+    merged.into_iter().unzip()
+}
+
+/// Map a 1-based `index` into a merged listing, whose rows are the positions `listed` of entries
+/// coming from the files `sources`, to the file of that entry and its 1-based index among the
+/// rows of that file alone. `None` when the index is out of range.
+fn split_merged_index(sources: &[usize], listed: &[usize], index: usize) -> Option<(usize, usize)> {
+    let pos = *listed.get(index.checked_sub(1)?)?;
+    let local = listed[..index].iter().filter(|&&i| sources[i] == sources[pos]).count();
+    Some((sources[pos], local))
+}
+
+/// `list`, `query`, `count`, `next` and `random` over several files at once (`--file` given
+/// more than once, the `files` setting, or `#include` lines). Entries are listed together, each
+/// description prefixed with the name of its file; `--porcelain` output names the file in a
+/// sixth field instead.
+fn run_merged(cli: Cli, files: &[PathBuf], clock: &Clock, context: &Filter, paging: bool) -> io::Result<()> {
+    let contents = files.iter().map(|f| read_file(f)).collect::<io::Result<Vec<String>>>()?;
+    let (sources, entries) = merge_entries(files, &contents);
+    let labelled: Vec<Entry> = entries
+        .iter()
+        .zip(&sources)
+        .map(|(e, &i)| {
+            let name = files[i].file_stem().map_or_else(|| files[i].display().to_string(), |s| s.to_string_lossy().into_owned());
+            Entry { desc: Cow::Owned(format!("{}: {}", name, e.desc)), ..e.clone() }
+        })
+        .collect();
+    let porcelain = |out: &mut String, rows: &[usize]| {
+        for &i in rows {
+            writeln!(out, "{}\t{}", porcelain_line(&entries[i]), files[sources[i]].display()).ok();
+        }
+    };
+    // the rows at these positions, like `render_titled_tables`
+    let render = |out: &mut String, rows: Vec<usize>, show_all: bool| {
+        if !cli.porcelain {
+            return render_titled_tables(out, rows.iter().map(|&i| &labelled[i]), show_all, false);
+        }
+        let (completed, open): (Vec<usize>, Vec<usize>) = rows.into_iter().partition(|&i| is_complete(&entries[i]));
+        porcelain(out, &open);
+        if show_all {
+            porcelain(out, &completed);
+        }
+        open.len() + if show_all { completed.len() } else { 0 }
+    };
+    // one picked row, numbered as in `list`
+    let render_one = |out: &mut String, pos: usize, number: usize| {
+        if cli.porcelain {
+            porcelain(out, &[pos]);
+        } else {
+            render_numbered_from(out, &[&labelled[pos]], number);
+        }
+    };
+
+    let mut out = String::new();
+    match cli.command {
+        None | Some(Commands::List { .. }) => {
+            let (limit, blocked, someday) = match cli.command {
+                Some(Commands::List { limit, blocked, someday }) => (limit, blocked, someday),
+                _ => (None, false, false),
+            };
+            let wanted = |e: &Entry| context.matches(e) && (!blocked || is_blocked(e)) && someday_shown(e, someday, cli.show_all);
+            let mut shown: Vec<usize> = (0..entries.len()).filter(|&i| wanted(&entries[i])).collect();
+            // like a limited single-file listing: the first n open entries, then completed ones
+            if let Some(n) = limit {
+                let (completed, open): (Vec<usize>, Vec<usize>) = shown.into_iter().partition(|&i| is_complete(&entries[i]));
+                let open: Vec<usize> = open.into_iter().take(n).collect();
+                let remaining = n - open.len();
+                shown = open.into_iter().chain(completed.into_iter().take(remaining)).collect();
+            }
+            render(&mut out, shown, cli.show_all);
+            output::emit(&out, cli.copy, paging)?;
+        }
+        Some(Commands::Query { criteria }) => {
+            if criteria.is_empty() {
+                eprintln!("{}", tr(Msg::QueryNeedsCriterion));
+                return Err(failed(1));
+            }
+            let filter = criteria.into_filter(clock.today())?;
+            let show_all = cli.show_all || filter.status.is_some_and(Status::is_closed);
+            let matching = (0..entries.len()).filter(|&i| filter.matches(&entries[i]) && context.matches(&entries[i])).collect();
+            let shown = render(&mut out, matching, show_all);
+            output::emit(&out, cli.copy, paging)?;
+            if shown == 0 {
+                return Err(failed(EXIT_NO_MATCH));
+            }
+        }
+        Some(Commands::Count { criteria, breakdown }) => {
+            let filter = criteria.into_filter(clock.today())?;
+            let show_all = cli.show_all || filter.status.is_some_and(|st| st.is_closed() || st == Status::Someday);
+            print_count(entries.iter().filter(|e| filter.matches(e) && context.matches(e)), clock, breakdown, show_all);
+        }
+        Some(Commands::Next {}) => match next_pick(&entries, context) {
+            Some((pos, number)) => {
+                render_one(&mut out, pos, number);
+                output::emit(&out, cli.copy, false)?;
+            }
+            None => println!("{}", tr(Msg::NothingToDo)),
+        },
+        Some(Commands::Random { tag, any }) => {
+            let filter = Filter {
+                tags: tag,
+                any,
+                ..Filter::default()
+            };
+            match random_pick(&entries, context, &filter) {
+                Some((pos, number)) => {
+                    render_one(&mut out, pos, number);
+                    output::emit(&out, cli.copy, false)?;
+                }
+                None => println!("{}", tr(Msg::NoMatchingOpen)),
+            }
+        }
+        _ => unreachable!("only listing commands span several files"),
+    }
+    Ok(())
+}
+
+/// Record a change of the data file in its audit log and, unless `keep` is 0, as a snapshot
+/// (with one of the state before it when there is none yet), keeping the `keep` most recent.
+/// The change itself has already been made, so failures are only reported. Remote lists keep no
+/// history (see [`is_remote`]).
+fn record_change(path: &Path, before: &str, after: &str, clock: &Clock, who: &str, command: &str, keep: usize) {
+    if is_remote() {
+        return;
+    }
+    if let Err(err) = audit::record(path, before, after, clock.now, who, command) {
+        eprintln!("Could not write the audit log {}: {}", audit::log_path(path).display(), err);
+    }
+    if keep == 0 || before == after {
+        return;
+    }
+    let saved = audit::snapshots(path).and_then(|existing| {
+        if existing.is_empty() {
+            audit::take_snapshot(path, before, clock.now)?;
+        }
+        audit::take_snapshot(path, after, clock.now)?;
+        audit::prune_snapshots(path, keep)
+    });
+    if let Err(err) = saved {
+        eprintln!("Could not save a snapshot in {}: {}", audit::snapshot_dir(path).display(), err);
+    }
+}
+
+/// Copy the data file to a timestamped backup before a bulk change and say where it went. Remote
+/// lists get no backups (see [`is_remote`]).
+pub(crate) fn backup_before_bulk(path: &Path, clock: &Clock) -> io::Result<()> {
+    if is_remote() {
+        return Ok(());
+    }
+    let backup = audit::backup(path, clock.now)?;
+    println!("{}", trf(Msg::BackupSaved, &[&backup.display()]));
+    Ok(())
+}
+
+/// The content of snapshot `number` of `path`, failing after printing an error when there is none.
+fn load_snapshot(path: &Path, number: u32) -> io::Result<String> {
+    audit::read_snapshot(path, number).map_err(|_| {
+        eprintln!("{}", trf(Msg::NoSuchSnapshot, &[&number]));
+        failed(1)
+    })
+}
+
+/// An entry on one line for reports: date (in the display format), description and tags.
+fn entry_summary(e: &Entry<'_>) -> String {
+    format!("{}  {}  {}", dates::display(e.date), e.desc, e.tags.join(","))
+        .trim_end()
+        .to_string()
+}
+
+/// Print removed lines with `-` and added lines with `+`, tabs widened for reading.
+fn write_diff(out: &mut String, removed: &[String], added: &[String]) {
+    for (sign, lines) in [("-", removed), ("+", added)] {
+        for line in lines {
+            writeln!(out, "  {} {}", sign, line.replace('\t', "  ")).ok();
+        }
+    }
+}
+
+/// The position in `entries` (as returned by `sorted_entries`) of the entry `next` suggests, and
+/// the number `list` shows for it, so it can be passed straight to `complete`.
+fn next_pick(entries: &[Entry<'_>], context: &Filter) -> Option<(usize, usize)> {
+    let e = next_entry(entries.iter().filter(|e| context.matches(e)))?;
+    let pos = entries.iter().position(|other| std::ptr::eq(other, e))?;
+    let number = listed_indices(entries, false, context).iter().position(|&i| i == pos).map_or(0, |p| p + 1);
+    Some((pos, number))
+}
+
+/// The position of a randomly picked open entry of `entries` matching `filter`, and its number
+/// in `list`.
+fn random_pick(entries: &[Entry<'_>], context: &Filter, filter: &Filter) -> Option<(usize, usize)> {
+    let candidates: Vec<(usize, usize)> = listed_indices(entries, false, context)
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, i)| filter.matches(&entries[i]))
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let (n, pos) = candidates[random_below(candidates.len())];
+    Some((pos, n + 1))
+}
+
+/// A random number in `0..n` (`n` must be non-zero). std's `RandomState` is seeded randomly per
+/// process, which is plenty for picking a task without pulling in a random number crate.
+fn random_below(n: usize) -> usize {
+    use std::hash::{BuildHasher, Hasher};
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (random % n as u64) as usize
+}
+
+/// Give `e` (read from `path`) the status `st` and rewrite its line, recording the day in a
+/// `done:` tag and `author` in a `done-by:` tag when it becomes done. When a repeating entry (`after:` tag) becomes done, its
+/// next occurrence is added too; its date is returned.
+fn change_status(
+    path: &Path,
+    e: &mut Entry<'_>,
+    st: Status,
+    clock: &Clock,
+    sorted_writes: bool,
+    author: Option<&str>,
+) -> io::Result<Option<NaiveDate>> {
+    let old = status(e);
+    let newly_done = st == Status::Done && old != Status::Done;
+    if st != Status::InProgress {
+        stop_tracking(e, clock);
+    }
+    set_status(e, st);
+    if newly_done {
+        set_tag_value(e, "done", &dates::store(clock.today()));
+    }
+    if newly_done && let Some(name) = author {
+        set_tag_value(e, "done-by", name);
+    }
+    rewrite_entry(path, e)?;
+    // entries tagged after:<interval> come back that long after being done
+    let Some(next) = next_after_completion(e, clock.today()).filter(|_| newly_done) else {
+        return Ok(None);
+    };
+    if sorted_writes {
+        write_entry_sorted(path, &next)?;
+    } else {
+        append_entry(path, &entry_to_line(&next, FileFormat::of(path)))?;
+    }
+    Ok(Some(next.date))
+}
+
+/// How much one day can hold: a number of entries and, optionally, minutes of estimated work.
+#[derive(Clone, Copy)]
+struct Capacity {
+    entries: usize,
+    minutes: Option<u32>,
+}
+
+impl Capacity {
+    /// The `day_capacity` and `day_hours` settings; unset limits are unlimited.
+    fn from_config(config: &Config) -> Capacity {
+        Capacity {
+            entries: if config.day_capacity > 0 { config.day_capacity } else { usize::MAX },
+            minutes: (config.day_hours > 0.0).then_some((config.day_hours * 60.0) as u32),
+        }
+    }
+
+    /// Print a warning when `count` entries with `minutes` of estimated work overload `date`.
+    fn warn_if_over(self, date: NaiveDate, count: usize, minutes: u32) {
+        if count > self.entries {
+            eprintln!("{}", trf(Msg::OverCapacity, &[&dates::display(date), &count, &self.entries]));
+        }
+        if let Some(max) = self.minutes
+            && minutes > max
+        {
+            let hours = |m: u32| format!("{:.1}", f64::from(m) / 60.0);
+            eprintln!("{}", trf(Msg::OverCapacityHours, &[&dates::display(date), &hours(minutes), &hours(max)]));
+        }
+    }
+}
+
+/// Warn when the open entries on `date` in the data file go over the day's capacity settings.
+fn warn_if_day_over_capacity(path: &Path, date: NaiveDate, config: &Config) -> io::Result<()> {
+    let capacity = Capacity::from_config(config);
+    if capacity.entries == usize::MAX && capacity.minutes.is_none() {
+        return Ok(());
+    }
+    let (mut count, mut minutes) = (0, 0);
+    for e in entries_iter(path)?.filter(|e| e.date == date && is_active(e)) {
+        count += 1;
+        minutes += estimate_minutes(&e).unwrap_or(0);
+    }
+    capacity.warn_if_over(date, count, minutes);
+    Ok(())
+}
+
+/// Number of entries and minutes of estimated work on each day.
+type DayLoad = BTreeMap<NaiveDate, (usize, u32)>;
+
+/// Spread the open entries due before `horizon` (overdue ones included) over the days from
+/// `today` so that no day goes over `capacity`, together with the undated ones when `undated`
+/// is set. Priority entries get the earliest room, and no entry moves before its own date. In-progress and blocked entries and those due later stay
+/// put but take up room on their day. Returns the entries whose date changes, with the new date,
+/// and the resulting number of entries and minutes of estimated work on each day.
+fn plan_schedule<'a, 'e>(
+    open: &[&'a Entry<'e>],
+    today: NaiveDate,
+    horizon: NaiveDate,
+    capacity: Capacity,
+    undated: bool,
+) -> (Vec<(&'a Entry<'e>, NaiveDate)>, DayLoad) {
+    let day_of = |e: &Entry| if undated && e.date == dates::UNDATED { today } else { e.date.max(today) };
+    let (fixed, mut movable): (Vec<&Entry>, Vec<&Entry>) = open
+        .iter()
+        .copied()
+        .partition(|e| day_of(e) >= horizon || status(e) == Status::InProgress || is_blocked(e));
+    let mut load = DayLoad::new();
+    for e in fixed {
+        let day = load.entry(day_of(e)).or_default();
+        day.0 += 1;
+        day.1 += estimate_minutes(e).unwrap_or(0);
+    }
+    movable.sort_by_key(|e| (!is_priority(e), e.date));
+
+    let mut moves = Vec::new();
+    for e in movable {
+        let minutes = estimate_minutes(e).unwrap_or(0);
+        let mut date = day_of(e);
+        // a day with nothing on it takes any entry, so this ends
+        while let Some(&(count, used)) = load.get(&date)
+            && (count >= capacity.entries || capacity.minutes.is_some_and(|max| used + minutes > max))
+        {
+            date = date.succ_opt().expect("date in range");
+        }
+        let day = load.entry(date).or_default();
+        day.0 += 1;
+        day.1 += minutes;
+        if date != e.date {
+            moves.push((e, date));
+        }
+    }
+    moves.sort_by_key(|(e, date)| (*date, e.line));
+    (moves, load)
+}
+
+/// Format of the `started:` time-tracking tag (UTC).
+const TIMESTAMP: &str = "%Y-%m-%dT%H:%MZ";
+
+/// If `e` has a running timer (a `started:` tag), stop it and add the elapsed minutes to its
+/// `spent:<minutes>m` tag.
+pub(crate) fn stop_tracking(e: &mut Entry<'_>, clock: &Clock) {
+    let Some(started) = e
+        .tags
+        .iter()
+        .find_map(|t| t.strip_prefix("started:"))
+        .and_then(|s| NaiveDateTime::parse_from_str(s, TIMESTAMP).ok())
+    else {
+        return;
+    };
+    let elapsed = (clock.now.naive_utc() - started).num_minutes().max(0);
+    let spent: i64 = e
+        .tags
+        .iter()
+        .find_map(|t| t.strip_prefix("spent:")?.strip_suffix('m')?.parse().ok())
+        .unwrap_or(0);
+    e.tags.retain(|t| !t.starts_with("started:") && !t.starts_with("spent:"));
+    e.tags.push(Arc::from(format!("spent:{}m", spent + elapsed)));
+}
+
+/// Move overdue open entries to today, keeping the file in order in sorted mode. Returns how many
+/// entries moved.
+fn roll(path: &Path, clock: &Clock, mark: bool, sorted_writes: bool) -> io::Result<usize> {
+    let moved = roll_overdue(path, clock, mark)?;
+    if sorted_writes && moved > 0 {
+        sort_file(path)?;
+    }
+    Ok(moved)
+}
+
+/// Positions in `entries` of the rows `list` shows (and numbers), given `--show-all` and the
+/// active context.
+fn listed_indices(entries: &[Entry<'_>], show_all: bool, context: &Filter) -> Vec<usize> {
+    let mut idxs = visible_indices(entries, show_all);
+    idxs.retain(|&i| context.matches(&entries[i]));
+    idxs
+}
+
+/// Whether `list` shows `e`: with `someday` only entries parked as someday, otherwise all but
+/// those unless `show_all` is set.
+fn someday_shown(e: &Entry<'_>, someday: bool, show_all: bool) -> bool {
+    let parked = status(e) == Status::Someday;
+    if someday { parked } else { show_all || !parked }
+}
+
+/// The INDEX of a command that picks one entry by its number in `list` (in `list --someday` for
+/// `activate`).
+fn picked_index(command: &mut Option<Commands>) -> Option<&mut usize> {
+    match command.as_mut()? {
+        Commands::Edit { index: Some(index), .. }
+        | Commands::Move { index: Some(index), .. }
+        | Commands::Status { index: Some(index), .. }
+        | Commands::Complete { index }
+        | Commands::Start { index, .. }
+        | Commands::Skip { index }
+        | Commands::Reschedule { index, .. }
+        | Commands::Activate { index }
+        | Commands::Annotate { index, .. }
+        | Commands::Show { index }
+        | Commands::Open { index, .. }
+        | Commands::Share { index, .. } => Some(index),
+        _ => None,
+    }
+}
+
+/// Map a 1-based index as printed by `list` to a position in `entries` (as returned by
+/// `sorted_entries`), failing after printing an error when it is out of range.
+fn resolve_index(entries: &[Entry<'_>], index: usize, show_all: bool, context: &Filter) -> io::Result<usize> {
+    let vis_idxs = listed_indices(entries, show_all, context);
+    if index == 0 || index > vis_idxs.len() {
+        eprintln!("{}", trf(Msg::IndexOutOfRange, &[&index, &vis_idxs.len()]));
+        return Err(failed(1));
+    }
+    Ok(vis_idxs[index - 1])
+}
+
+/// Every field of `e` on its own labelled line: the plain tags and the `key:value` metadata
+/// apart, and the line as it is stored in `path`.
+fn entry_detail(e: &Entry<'_>, path: &Path) -> String {
+    const STATUS_TAGS: [&str; 3] = ["complete", "in-progress", "waiting"];
+    let mut rows: Vec<(String, String)> = vec![
+        (tr(Msg::ColDate).to_string(), dates::display(e.date)),
+        (tr(Msg::ColTask).to_string(), e.desc.to_string()),
+        (tr(Msg::ColStatus).to_string(), status(e).name().to_string()),
+    ];
+    let tags: Vec<&str> = e
+        .tags
+        .iter()
+        .map(|t| &**t)
+        .filter(|t| !t.contains(':') && !STATUS_TAGS.iter().any(|s| t.eq_ignore_ascii_case(s)))
+        .collect();
+    if !tags.is_empty() {
+        rows.push((tr(Msg::ColTags).to_string(), tags.join(", ")));
+    }
+    if let Some(p) = project(e) {
+        rows.push((tr(Msg::ColProject).to_string(), p.to_string()));
+    }
+    if let Some(interval) = e.tags.iter().find_map(|t| t.strip_prefix("after:")) {
+        rows.push((tr(Msg::ColRepeats).to_string(), trf(Msg::AfterCompletion, &[&interval])));
+    }
+    for t in e.tags.iter() {
+        if let Some((key, value)) = t.split_once(':')
+            && !["project", "status", "after", "note"].contains(&key)
+        {
+            rows.push((key.to_string(), value.to_string()));
+        }
+    }
+    for (time, text) in annotations(e) {
+        rows.push((tr(Msg::ColNote).to_string(), format!("{}  {}", time, text)));
+    }
+    if let Some(n) = e.line {
+        rows.push((tr(Msg::ColLine).to_string(), format!("{} ({})", n + 1, path.display())));
+    }
+    rows.push((tr(Msg::ColRaw).to_string(), e.raw_line.to_string()));
+
+    let width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(label, value)| format!("{:<width$}  {}\n", format!("{}:", label), value, width = width + 1))
+        .collect()
+}
+
+/// Parse all entries in `content` in list order (see `list_order`).
+fn sorted_entries(content: &str, format: FileFormat) -> Vec<Entry<'_>> {
+    let mut entries = parse_entries(content, format);
+    entries.sort_by_key(list_order);
+    entries
+}
+
 /// Returns the persisted default file path or prompts the user to enter one and persists it.
 fn get_or_ask_default_file() -> io::Result<PathBuf> {
     use std::io::{Write, stdin};
@@ -541,12 +2902,12 @@ fn get_or_ask_default_file() -> io::Result<PathBuf> {
     config_paths.push(PathBuf::from(".ironlist_default"));
 
     for cfg in &config_paths {
-        if cfg.exists() {
-            if let Ok(s) = std::fs::read_to_string(cfg) {
-                let trimmed = s.trim();
-                if !trimmed.is_empty() {
-                    return Ok(PathBuf::from(trimmed));
-                }
+        if cfg.exists()
+            && let Ok(s) = std::fs::read_to_string(cfg)
+        {
+            let trimmed = s.trim();
+            if !trimmed.is_empty() {
+                return Ok(PathBuf::from(trimmed));
             }
         }
     }
@@ -554,7 +2915,7 @@ fn get_or_ask_default_file() -> io::Result<PathBuf> {
     // Not found: prompt the user
     eprintln!("No default data file configured. Please enter the path to your ironlist file:");
     let mut input = String::new();
-    stdin().read_line(&mut input).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    stdin().read_line(&mut input).map_err(io::Error::other)?;
     let entered = input.trim();
     if entered.is_empty() {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "No path entered"));
@@ -563,7 +2924,7 @@ fn get_or_ask_default_file() -> io::Result<PathBuf> {
     let path = PathBuf::from(entered);
 
     // Persist into the first available config path (prefer home)
-    if let Some(cfg) = config_paths.get(0) {
+    if let Some(cfg) = config_paths.first() {
         if let Some(parent) = cfg.parent() {
             std::fs::create_dir_all(parent).ok();
         }
@@ -575,7 +2936,7 @@ fn get_or_ask_default_file() -> io::Result<PathBuf> {
     Ok(path)
 }
 
-fn persist_default_path(path: &PathBuf) -> io::Result<()> {
+fn persist_default_path(path: &Path) -> io::Result<()> {
     let cfg = if let Some(home) = dirs::home_dir() {
         home.join(".ironlist_default")
     } else {
@@ -594,12 +2955,12 @@ fn persist_default_path(path: &PathBuf) -> io::Result<()> {
 fn read_saved_default() -> Option<PathBuf> {
     if let Some(home) = dirs::home_dir() {
         let cfg = home.join(".ironlist_default");
-        if cfg.exists() {
-            if let Ok(s) = std::fs::read_to_string(cfg) {
-                let t = s.trim();
-                if !t.is_empty() {
-                    return Some(PathBuf::from(t));
-                }
+        if cfg.exists()
+            && let Ok(s) = std::fs::read_to_string(cfg)
+        {
+            let t = s.trim();
+            if !t.is_empty() {
+                return Some(PathBuf::from(t));
             }
         }
     }
@@ -626,3 +2987,55 @@ fn clear_saved_default() -> io::Result<()> {
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_index_maps_to_the_entry_in_its_own_file() {
+        // rows of the merged listing, by file: 0 1 0 2 1
+        let sources = [0, 1, 0, 2, 1];
+        let listed = [0, 1, 2, 3, 4];
+        assert_eq!(split_merged_index(&sources, &listed, 1), Some((0, 1)));
+        assert_eq!(split_merged_index(&sources, &listed, 2), Some((1, 1)));
+        assert_eq!(split_merged_index(&sources, &listed, 3), Some((0, 2)));
+        assert_eq!(split_merged_index(&sources, &listed, 4), Some((2, 1)));
+        assert_eq!(split_merged_index(&sources, &listed, 5), Some((1, 2)));
+    }
+
+    #[test]
+    fn merged_index_counts_only_listed_rows() {
+        // positions 1 and 3 (completed, or outside the context) are not listed
+        let sources = [0, 0, 1, 0, 0];
+        let listed = [0, 2, 4];
+        assert_eq!(split_merged_index(&sources, &listed, 2), Some((1, 1)));
+        assert_eq!(split_merged_index(&sources, &listed, 3), Some((0, 2)));
+    }
+
+    #[test]
+    fn merged_index_out_of_range() {
+        let sources = [0, 1];
+        let listed = [0, 1];
+        assert_eq!(split_merged_index(&sources, &listed, 0), None);
+        assert_eq!(split_merged_index(&sources, &listed, 3), None);
+    }
+
+    #[test]
+    fn merged_entries_are_sorted_like_one_list() {
+        let files = [PathBuf::from("work.txt"), PathBuf::from("home.txt")];
+        let contents = [
+            "2026-10-20\tReport\n-\tUndated idea\n2026-10-18\tCall\tstatus:done\n".to_string(),
+            "2026-10-19\tGroceries\n2026-10-25\tPaint\tstatus:in-progress\n".to_string(),
+        ];
+        let (sources, entries) = merge_entries(&files, &contents);
+        let descs: Vec<&str> = entries.iter().map(|e| e.desc.as_ref()).collect();
+        assert_eq!(descs, ["Paint", "Call", "Groceries", "Report", "Undated idea"]);
+        assert_eq!(sources, [1, 0, 1, 0, 0]);
+
+        // `complete 2` in the merged view is Groceries, the second listed entry of home.txt, after Paint
+        let listed = listed_indices(&entries, false, &Filter::default());
+        assert_eq!(split_merged_index(&sources, &listed, 2), Some((1, 2)));
+        assert_eq!(split_merged_index(&sources, &listed, 3), Some((0, 1)));
+    }
+}
@@ -0,0 +1,231 @@
+//! Keeping the list in a Markdown file, e.g. a note in an Obsidian vault, instead of the
+//! tab-separated format.
+//!
+//! Every checklist item is an entry: `- [ ] Call the bank #admin [status:: waiting] 📅 2026-10-16`.
+//! `[x]` marks it done, trailing `#words` are its tags, other `key:value` tags are written as
+//! inline fields such as `[status:: waiting]`, `📅 DATE` is its date and `✅ DATE` the day it was
+//! done, as the Obsidian Tasks plugin writes them. A description ending in text that reads like
+//! metadata gets a backslash in front of it. Every other line (headings, prose, YAML
+//! frontmatter) is not an entry and is left as it is.
+//!
+//! The format is chosen per file from its extension (see [`is_markdown`] and
+//! [`FileFormat::of`](crate::FileFormat::of)), so a Markdown list can still be moved to, merged
+//! with or imported from tab-separated files.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::{Entry, TagInterner, dates};
+
+const DUE: &str = "📅";
+const DONE: &str = "✅";
+
+/// Whether `path` names a Markdown file (`.md` or `.markdown`).
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Split a checklist item into its indentation and bullet (`- `, `* ` or `+ `), whether it is
+/// checked, and the text after the box.
+fn split_item(line: &str) -> Option<(&str, bool, &str)> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let after_bullet = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* ")).or_else(|| rest.strip_prefix("+ "))?;
+    let (checked, text) = if let Some(text) = after_bullet.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = after_bullet.strip_prefix("[x]").or_else(|| after_bullet.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+    let prefix_len = line.len() - after_bullet.len();
+    Some((&line[..prefix_len], checked, text.trim()))
+}
+
+/// Metadata at the end of a checklist item.
+enum Meta<'a> {
+    Field(&'a str, &'a str),
+    Tag(&'a str),
+    Due(NaiveDate),
+    Done(NaiveDate),
+}
+
+/// The metadata `text` ends with: where it starts, how many backslashes escape it there, and
+/// what it is. Escaped metadata is part of the description (see [`escape`]).
+fn trailing(text: &str) -> Option<(usize, usize, Meta<'_>)> {
+    let text = text.trim_end();
+    let (at, meta) = if text.ends_with(']')
+        && let Some(open) = text.rfind('[')
+        && let Some((key, value)) = text[open + 1..text.len() - 1].split_once("::")
+    {
+        (open, Meta::Field(key.trim(), value.trim()))
+    } else {
+        let (head, word) = text.rsplit_once(char::is_whitespace).unwrap_or(("", text));
+        let word = word.trim_start_matches('\\');
+        if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            (text.len() - word.len(), Meta::Tag(tag))
+        } else {
+            let day = NaiveDate::parse_from_str(word, dates::ISO).ok()?;
+            let head = head.trim_end();
+            if let Some(before) = head.strip_suffix(DUE) {
+                (before.len(), Meta::Due(day))
+            } else {
+                (head.strip_suffix(DONE)?.len(), Meta::Done(day))
+            }
+        }
+    };
+    let escapes = at - text[..at].trim_end_matches('\\').len();
+    Some((at, escapes, meta))
+}
+
+/// `desc` with a backslash before each `#tag`, `[key:: value]` field and 📅/✅ date at its end
+/// (escaped ones too), so that they are read back as part of the description.
+fn escape(desc: &str) -> Cow<'_, str> {
+    let mut out = Cow::Borrowed(desc);
+    let mut end = desc.len();
+    while let Some((at, escapes, _)) = trailing(&desc[..end]) {
+        out.to_mut().insert(at, '\\');
+        end = at - escapes;
+    }
+    out
+}
+
+/// Undo [`escape`].
+fn unescape(desc: &str) -> Cow<'_, str> {
+    let mut out = Cow::Borrowed(desc);
+    let mut end = desc.len();
+    while let Some((at, escapes, _)) = trailing(&desc[..end]).filter(|&(_, escapes, _)| escapes > 0) {
+        out.to_mut().remove(at - 1);
+        end = at - escapes;
+    }
+    out
+}
+
+/// Parse a checklist item; any other line yields `None`.
+pub(crate) fn parse<'a>(line: &'a str, interner: &mut TagInterner) -> Option<Entry<'a>> {
+    let (_, checked, mut text) = split_item(line)?;
+    let mut date = dates::UNDATED;
+    let mut trailing_tags: Vec<String> = Vec::new();
+    // metadata is read from the end of the item, up to the first word that isn't any
+    while let Some((at, 0, meta)) = trailing(text) {
+        match meta {
+            Meta::Field("tag", value) => trailing_tags.push(value.to_string()),
+            Meta::Field(key, value) => trailing_tags.push(format!("{}:{}", key, value)),
+            Meta::Tag(tag) => trailing_tags.push(tag.to_string()),
+            Meta::Due(day) => date = day,
+            Meta::Done(day) => trailing_tags.push(format!("done:{}", dates::store(day))),
+        }
+        text = &text[..at];
+    }
+    let desc = text.trim();
+    if desc.is_empty() {
+        return None;
+    }
+    let mut tags: Vec<_> = trailing_tags.iter().rev().map(|t| interner.intern(t)).collect();
+    if checked && !tags.iter().any(|t| t.eq_ignore_ascii_case("complete")) {
+        tags.insert(0, interner.intern("complete"));
+    }
+    Some(Entry {
+        date,
+        desc: unescape(desc),
+        tags,
+        raw_line: Cow::Borrowed(line),
+        line: None,
+    })
+}
+
+/// `e` as a checklist item, keeping the indentation and bullet of the line it was read from.
+pub(crate) fn format(e: &Entry) -> String {
+    let prefix = split_item(&e.raw_line).map_or("- ", |(prefix, _, _)| prefix);
+    let checked = e.tags.iter().any(|t| t.eq_ignore_ascii_case("complete"));
+    let mut out = format!("{}[{}] {}", prefix, if checked { 'x' } else { ' ' }, escape(&e.desc));
+    let mut done = None;
+    for t in e.tags.iter().filter(|t| !t.eq_ignore_ascii_case("complete")) {
+        if let Some(day) = t.strip_prefix("done:").and_then(|d| NaiveDate::parse_from_str(d, dates::ISO).ok()) {
+            done = Some(day);
+        } else if let Some((key, value)) = t.split_once(':') {
+            out.push_str(&format!(" [{}:: {}]", key, value));
+        } else if t.contains(|c: char| c.is_whitespace() || "#[]".contains(c)) {
+            out.push_str(&format!(" [tag:: {}]", t));
+        } else {
+            out.push_str(&format!(" #{}", t));
+        }
+    }
+    if e.date != dates::UNDATED {
+        out.push_str(&format!(" {} {}", DUE, dates::store(e.date)));
+    }
+    if let Some(day) = done {
+        out.push_str(&format!(" {} {}", DONE, dates::store(day)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// `desc` with `tags` and `date` written as a checklist item and read back.
+    fn round_trip(desc: &str, tags: &[&str], date: NaiveDate) -> (String, Entry<'static>) {
+        let mut interner = TagInterner::new();
+        let e = Entry {
+            date,
+            desc: Cow::Borrowed(desc),
+            tags: tags.iter().map(|t| interner.intern(t)).collect(),
+            raw_line: Cow::Borrowed(""),
+            line: None,
+        };
+        let line = format(&e);
+        let back = parse(&line, &mut interner).expect("a checklist item").into_owned();
+        (line, back)
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 21).unwrap();
+        let (line, back) = round_trip("Call the bank", &["admin", "status:waiting", "needs space"], day);
+        assert_eq!(line, "- [ ] Call the bank #admin [status:: waiting] [tag:: needs space] 📅 2026-10-21");
+        assert_eq!(back.desc, "Call the bank");
+        assert_eq!(back.tags, ["admin", "status:waiting", "needs space"].map(Arc::from));
+        assert_eq!(back.date, day);
+    }
+
+    #[test]
+    fn description_keeps_what_looks_like_metadata_at_its_end() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 21).unwrap();
+        for desc in [
+            "Reply to PR #34",
+            "Sort out #home #garden",
+            "Note the field [owner:: me]",
+            "Moved from 📅 2026-10-01",
+            "Was done ✅ 2026-10-02 #twice",
+            r"Keep \#literal",
+            "#42",
+        ] {
+            let (line, back) = round_trip(desc, &["work"], day);
+            assert_eq!(back.desc, desc, "{}", line);
+            assert_eq!(back.tags, [Arc::from("work")], "{}", line);
+            assert_eq!(back.date, day, "{}", line);
+            let (line, back) = round_trip(desc, &[], dates::UNDATED);
+            assert_eq!(back.desc, desc, "{}", line);
+            assert!(back.tags.is_empty(), "{}", line);
+        }
+        let (line, _) = round_trip("Reply to PR #34", &["work"], day);
+        assert_eq!(line, r"- [ ] Reply to PR \#34 #work 📅 2026-10-21");
+    }
+
+    #[test]
+    fn metadata_in_the_middle_stays_in_the_description() {
+        let mut interner = TagInterner::new();
+        let e = parse("- [x] Fix #12 before [due:: friday] ships #work ✅ 2026-10-20", &mut interner).unwrap();
+        assert_eq!(e.desc, "Fix #12 before [due:: friday] ships");
+        assert_eq!(e.tags, ["complete", "work", "done:2026-10-20"].map(Arc::from));
+    }
+}
@@ -0,0 +1,191 @@
+//! Where rendered CLI output goes: straight to stdout, through a pager, or onto the clipboard.
+
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+/// Decide once whether rendered output may contain ANSI styling: only when it goes straight to a
+/// terminal (`to_terminal`) and `NO_COLOR` is not set.
+pub fn set_color(to_terminal: bool) {
+    COLOR
+        .set(to_terminal && io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none())
+        .ok();
+}
+
+/// ANSI codes to start and end dimmed text, or empty strings when styling is off.
+pub fn dim() -> (&'static str, &'static str) {
+    if COLOR.get().copied().unwrap_or(false) {
+        ("\x1b[2m", "\x1b[0m")
+    } else {
+        ("", "")
+    }
+}
+
+/// Print `text`, piping it through a pager (like git does) when stdout is a terminal and the text
+/// is taller than the terminal. `$PAGER` picks the pager (default `less`); `LESS` defaults to `FRX`
+/// so less still exits by itself when the output turns out to fit on one screen.
+pub fn print_paged(text: &str, paging: bool) {
+    // $LINES is only a hint; when it's missing the pager (less -F) decides whether to page
+    let fits = std::env::var("LINES")
+        .ok()
+        .and_then(|l| l.parse::<usize>().ok())
+        .is_some_and(|height| text.lines().count() < height);
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next().filter(|_| paging && !fits && io::stdout().is_terminal()) else {
+        print!("{}", text);
+        return;
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(words).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+    match cmd.spawn() {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // the user quitting the pager early closes the pipe; that's not an error
+                stdin.write_all(text.as_bytes()).ok();
+            }
+            child.wait().ok();
+        }
+        // no usable pager (e.g. `less` missing on Windows): print directly
+        Err(_) => print!("{}", text),
+    }
+}
+
+/// Width of the terminal in columns: `$COLUMNS`, else what `stty size` reports for the
+/// controlling terminal, else 80.
+pub fn terminal_width() -> usize {
+    if let Some(cols) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return cols;
+    }
+    std::fs::File::open("/dev/tty")
+        .ok()
+        .and_then(|tty| Command::new("stty").arg("size").stdin(tty).stderr(Stdio::null()).output().ok())
+        .and_then(|out| {
+            let size = String::from_utf8_lossy(&out.stdout).into_owned();
+            size.split_whitespace().nth(1)?.parse().ok()
+        })
+        .unwrap_or(80)
+}
+
+/// Clipboard tools to try, in order, for the current platform.
+fn clipboard_commands() -> &'static [&'static [&'static str]] {
+    if cfg!(target_os = "windows") {
+        &[&["clip"]]
+    } else if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else {
+        &[&["wl-copy"], &["xclip", "-selection", "clipboard"], &["xsel", "--clipboard", "--input"]]
+    }
+}
+
+/// Put `text` on the system clipboard using the platform's clipboard tool (`clip` on Windows,
+/// `pbcopy` on macOS, `wl-copy`, `xclip` or `xsel` elsewhere).
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    for args in clipboard_commands() {
+        let mut child = match Command::new(args[0])
+            .args(&args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("{} exited with {}", args[0], status)));
+        }
+        return Ok(());
+    }
+    let tried: Vec<&str> = clipboard_commands().iter().map(|c| c[0]).collect();
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no clipboard tool found (tried {})", tried.join(", ")),
+    ))
+}
+
+/// Open `path` at 1-based `line` in `$VISUAL` or `$EDITOR` (as `editor +LINE FILE`), or with
+/// the default handler when neither is set.
+pub fn open_in_editor(path: &Path, line: usize) -> io::Result<()> {
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_default();
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        return open_external(&path.to_string_lossy());
+    };
+    let status = Command::new(program).args(words).arg(format!("+{}", line)).arg(path).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+/// Open `target` (a URL or file) with the platform's default handler: `start` on Windows, `open`
+/// on macOS, `xdg-open` elsewhere.
+pub fn open_external(target: &str) -> io::Result<()> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let status = cmd
+        .arg(target)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(err.kind(), format!("`{}` is not installed", program)),
+            _ => err,
+        })?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+/// Render `text` as a terminal QR code using the `qrencode` tool.
+pub fn render_qr(text: &str) -> io::Result<String> {
+    let mut child = Command::new("qrencode")
+        .args(["-t", "UTF8", "-o", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(err.kind(), "`qrencode` is not installed"),
+            _ => err,
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    if !out.status.success() {
+        return Err(io::Error::other(format!("qrencode exited with {}", out.status)));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Send rendered output to the clipboard when `copy` is set, otherwise print it (paged if enabled).
+pub fn emit(text: &str, copy: bool, paging: bool) -> io::Result<()> {
+    if !copy {
+        print_paged(text, paging);
+        return Ok(());
+    }
+    copy_to_clipboard(text).map_err(|err| io::Error::other(format!("Could not copy to the clipboard: {}", err)))?;
+    println!("Copied {} line(s) to the clipboard", text.lines().count());
+    Ok(())
+}
@@ -0,0 +1,414 @@
+//! A data file kept on another machine: over SSH (`ssh://[user@]host[:port]/path`) or in S3 or
+//! another object store (`s3://bucket/key`).
+//!
+//! The file is fetched into a local cache before a command runs, and the command works on that
+//! copy. When the copy changed, it is uploaded again, but only if the remote file is still what
+//! was fetched, so a change made from another machine in the meantime is never overwritten.
+//! Everything goes through the system's `ssh` and `aws` clients, so keys, agents, profiles and
+//! credentials are found the way they always are.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remote {
+    Ssh(SshFile),
+    S3(S3Object),
+}
+
+/// A remote file's content as fetched, with the version it had.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetched {
+    pub content: Vec<u8>,
+    /// The object's ETag, for object stores.
+    pub etag: Option<String>,
+}
+
+impl Remote {
+    /// Parse an `ssh://` or `s3://` URL; anything else yields `None`.
+    pub fn parse(url: &str) -> Option<Remote> {
+        SshFile::parse(url).map(Remote::Ssh).or_else(|| S3Object::parse(url).map(Remote::S3))
+    }
+
+    /// Where the local working copy is kept: under the user's cache directory, by location.
+    pub fn cache_path(&self) -> PathBuf {
+        let location = match self {
+            Remote::Ssh(f) => format!("{}{}", f.host, f.path),
+            Remote::S3(o) => format!("s3_{}_{}", o.bucket, o.key),
+        };
+        let name: String = location
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || ".-_@".contains(c) { c } else { '_' })
+            .collect();
+        dirs::cache_dir().unwrap_or_else(std::env::temp_dir).join("iron-list").join("remote").join(name)
+    }
+
+    pub fn download(&self) -> io::Result<Fetched> {
+        match self {
+            Remote::Ssh(f) => Ok(Fetched { content: f.download()?, etag: None }),
+            Remote::S3(o) => o.download(&self.cache_path()),
+        }
+    }
+
+    /// Replace the remote file with `content`, provided it is still as `fetched`.
+    pub fn upload(&self, content: &[u8], fetched: &Fetched) -> io::Result<()> {
+        match self {
+            Remote::Ssh(f) => f.upload(content, &fetched.content),
+            Remote::S3(o) => o.upload(&self.cache_path(), content, fetched.etag.as_deref()),
+        }
+    }
+}
+
+/// `program` (`ssh` or `aws`) as a command to run. Tests swap in stand-ins that work on local
+/// directories.
+fn command(program: &str) -> Command {
+    #[cfg(test)]
+    if let Some(dir) = tests::FAKE_BIN.get() {
+        return Command::new(dir.join(program));
+    }
+    Command::new(program)
+}
+
+/// A file reached over SSH. It is uploaded under a lock directory next to it and written to a
+/// temporary file that is then renamed over the original, so readers never see half a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshFile {
+    /// `host` or `user@host`.
+    pub host: String,
+    pub port: Option<u16>,
+    /// Path on the remote machine; `~/...` is relative to the home directory there.
+    pub path: String,
+}
+
+/// `s` quoted for a POSIX shell; the remote side runs commands through one. A leading `~/` is
+/// left outside the quotes so the shell still expands it.
+fn shell_quote(s: &str) -> String {
+    match s.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None => format!("'{}'", s.replace('\'', r"'\''")),
+    }
+}
+
+impl SshFile {
+    /// Parse an `ssh://` URL; anything else yields `None`. `ssh://host/~/list.txt` names a file
+    /// in the remote home directory, `ssh://host/srv/list.txt` an absolute path.
+    pub fn parse(url: &str) -> Option<SshFile> {
+        let rest = url.strip_prefix("ssh://")?;
+        let (authority, path) = rest.split_at(rest.find('/')?);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (authority, None),
+        };
+        let path = path.strip_prefix('/').filter(|p| p.starts_with("~/")).unwrap_or(path);
+        if host.is_empty() || path.len() < 2 {
+            return None;
+        }
+        Some(SshFile { host: host.to_string(), port, path: path.to_string() })
+    }
+
+    /// Run `script` with the remote shell, feeding it `input`, and return what it printed.
+    fn run(&self, script: &str, input: Option<&[u8]>) -> io::Result<Vec<u8>> {
+        let mut cmd = command("ssh");
+        if let Some(port) = self.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+        let mut child = cmd
+            .args(["-o", "BatchMode=yes", "--", &self.host, script])
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => io::Error::new(err.kind(), "`ssh` is not installed"),
+                _ => err,
+            })?;
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+            stdin.write_all(input)?;
+        }
+        let out = child.wait_with_output()?;
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            return Err(io::Error::other(format!("{}: {}", self.host, stderr.trim())));
+        }
+        Ok(out.stdout)
+    }
+
+    /// The remote file's current content.
+    pub fn download(&self) -> io::Result<Vec<u8>> {
+        self.run(&format!("cat -- {}", shell_quote(&self.path)), None)
+    }
+
+    /// Replace the remote file with `content`, provided it still holds `fetched`. Fails without
+    /// writing when another machine holds the lock or changed the file in the meantime.
+    pub fn upload(&self, content: &[u8], fetched: &[u8]) -> io::Result<()> {
+        let (file, lock, tmp) = (
+            shell_quote(&self.path),
+            shell_quote(&format!("{}.lock", self.path)),
+            shell_quote(&format!("{}.upload", self.path)),
+        );
+        self.run(&format!("mkdir -- {}", lock), None).map_err(|err| {
+            io::Error::other(format!("{} is locked by another writer ({}); remove {}.lock if it is stale", self.path, err, self.path))
+        })?;
+        let written = self.download().and_then(|current| {
+            if current != fetched {
+                return Err(io::Error::other(format!("{} was changed on {} since it was fetched; run the command again", self.path, self.host)));
+            }
+            self.run(&format!("cat > {} && mv -f -- {} {}", tmp, tmp, file), Some(content)).map(drop)
+        });
+        let unlocked = self.run(&format!("rmdir -- {}", lock), None);
+        written.and(unlocked.map(drop))
+    }
+}
+
+/// An object in S3 or a compatible store, handled with the AWS CLI (`aws s3api`). Credentials,
+/// region and endpoint (`AWS_ENDPOINT_URL` for other stores) are resolved by the CLI. Uploads are
+/// conditional on the ETag seen when the object was fetched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Object {
+    pub bucket: String,
+    pub key: String,
+}
+
+/// The `ETag` field of the JSON the AWS CLI prints, with its quotes unescaped.
+fn etag_field(json: &str) -> Option<String> {
+    let start = json.find("\"ETag\"")?;
+    let rest = json[start + 6..].trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            '"' => return Some(value),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+impl S3Object {
+    /// Parse an `s3://bucket/key` URL; anything else yields `None`.
+    pub fn parse(url: &str) -> Option<S3Object> {
+        let (bucket, key) = url.strip_prefix("s3://")?.split_once('/')?;
+        if bucket.is_empty() || key.is_empty() {
+            return None;
+        }
+        Some(S3Object { bucket: bucket.to_string(), key: key.to_string() })
+    }
+
+    /// Run `aws s3api <operation>` on the object with `args` and return what it printed.
+    fn s3api(&self, operation: &str, args: &[&str]) -> io::Result<String> {
+        let out = command("aws")
+            .args(["s3api", operation, "--bucket", &self.bucket, "--key", &self.key, "--output", "json"])
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => io::Error::new(err.kind(), "`aws` (the AWS CLI) is not installed"),
+                _ => err,
+            })?;
+        if !out.status.success() {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            return Err(io::Error::other(stderr.trim().to_string()));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    }
+
+    /// The object's content and ETag, downloaded through a temporary file next to `local`.
+    fn download(&self, local: &Path) -> io::Result<Fetched> {
+        let tmp = crate::sidecar_path(local, "download");
+        if let Some(parent) = tmp.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let fetched = self
+            .s3api("get-object", &[&tmp.to_string_lossy()])
+            .and_then(|printed| Ok(Fetched { content: std::fs::read(&tmp)?, etag: etag_field(&printed) }));
+        std::fs::remove_file(&tmp).ok();
+        fetched
+    }
+
+    /// Put `content` as the object, provided its ETag is still `etag`.
+    fn upload(&self, local: &Path, content: &[u8], etag: Option<&str>) -> io::Result<()> {
+        let tmp = crate::sidecar_path(local, "upload");
+        std::fs::write(&tmp, content)?;
+        let body = tmp.to_string_lossy().into_owned();
+        let mut args = vec!["--body", &body];
+        if let Some(etag) = etag {
+            args.extend(["--if-match", etag]);
+        }
+        let put = self.s3api("put-object", &args);
+        std::fs::remove_file(&tmp).ok();
+        put.map(drop).map_err(|err| {
+            if err.to_string().contains("PreconditionFailed") {
+                io::Error::other(format!("s3://{}/{} was changed since it was fetched; run the command again", self.bucket, self.key))
+            } else {
+                err
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    /// Directory holding the stand-in `ssh` and `aws` scripts, once they are written.
+    pub(super) static FAKE_BIN: OnceLock<PathBuf> = OnceLock::new();
+
+    /// Runs the remote script locally, with `$HOME` the directory `hosts/<host>` next to it.
+    const FAKE_SSH: &str = include_str!("../tests/fake/ssh.sh");
+
+    /// Keeps objects as files under `buckets/<bucket>/<key>`, their checksum as the ETag.
+    const FAKE_AWS: &str = include_str!("../tests/fake/aws.sh");
+
+    /// The directory of the stand-in clients, written on first use.
+    fn fake_bin() -> &'static Path {
+        FAKE_BIN.get_or_init(|| {
+            use std::os::unix::fs::PermissionsExt;
+            let dir = std::env::temp_dir().join(format!("iron-list-remote-{}", std::process::id()));
+            std::fs::remove_dir_all(&dir).ok();
+            std::fs::create_dir_all(&dir).unwrap();
+            for (name, script) in [("ssh", FAKE_SSH), ("aws", FAKE_AWS)] {
+                std::fs::write(dir.join(name), script).unwrap();
+                std::fs::set_permissions(dir.join(name), std::fs::Permissions::from_mode(0o755)).unwrap();
+            }
+            dir
+        })
+    }
+
+    /// A fresh home directory for the SSH host `host`, holding `list.txt` with `content`.
+    fn ssh_host(host: &str, content: &str) -> (SshFile, PathBuf) {
+        let home = fake_bin().join("hosts").join(host);
+        std::fs::remove_dir_all(&home).ok();
+        std::fs::create_dir_all(&home).unwrap();
+        std::fs::write(home.join("list.txt"), content).unwrap();
+        (SshFile::parse(&format!("ssh://{}/~/list.txt", host)).unwrap(), home)
+    }
+
+    /// A fresh bucket `bucket` holding the object `list.txt` with `content`, and a directory for
+    /// the local copy.
+    fn s3_bucket(bucket: &str, content: &str) -> (S3Object, PathBuf, PathBuf) {
+        let dir = fake_bin().join("buckets").join(bucket);
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("list.txt"), content).unwrap();
+        let local = fake_bin().join("cache").join(bucket);
+        std::fs::create_dir_all(&local).unwrap();
+        (S3Object::parse(&format!("s3://{}/list.txt", bucket)).unwrap(), dir.join("list.txt"), local.join("list.txt"))
+    }
+
+    #[test]
+    fn parses_ssh_urls() {
+        assert_eq!(
+            SshFile::parse("ssh://me@box:2222/~/lists/todo.txt"),
+            Some(SshFile { host: "me@box".to_string(), port: Some(2222), path: "~/lists/todo.txt".to_string() })
+        );
+        assert_eq!(
+            SshFile::parse("ssh://box/srv/todo.txt"),
+            Some(SshFile { host: "box".to_string(), port: None, path: "/srv/todo.txt".to_string() })
+        );
+        assert_eq!(SshFile::parse("ssh://box:port/todo.txt"), None);
+        assert_eq!(SshFile::parse("ssh://box/"), None);
+        assert_eq!(SshFile::parse("ssh:///todo.txt"), None);
+        assert_eq!(SshFile::parse("box:/todo.txt"), None);
+    }
+
+    #[test]
+    fn quotes_for_the_remote_shell() {
+        assert_eq!(shell_quote("/srv/my list.txt"), "'/srv/my list.txt'");
+        assert_eq!(shell_quote("it's.txt"), r"'it'\''s.txt'");
+        assert_eq!(shell_quote("~/todo $HOME.txt"), "~/'todo $HOME.txt'");
+    }
+
+    #[test]
+    fn ssh_upload_replaces_the_file_and_unlocks() {
+        let (file, home) = ssh_host("upload", "2026-10-16\tOld\n");
+        let fetched = file.download().unwrap();
+        assert_eq!(fetched, b"2026-10-16\tOld\n");
+        file.upload(b"2026-10-16\tNew\n", &fetched).unwrap();
+        assert_eq!(std::fs::read_to_string(home.join("list.txt")).unwrap(), "2026-10-16\tNew\n");
+        assert!(!home.join("list.txt.lock").exists());
+        assert!(!home.join("list.txt.upload").exists());
+    }
+
+    #[test]
+    fn ssh_upload_refuses_a_file_changed_since_fetched() {
+        let (file, home) = ssh_host("changed", "2026-10-16\tOld\n");
+        let fetched = file.download().unwrap();
+        std::fs::write(home.join("list.txt"), "2026-10-16\tFrom elsewhere\n").unwrap();
+        let err = file.upload(b"2026-10-16\tNew\n", &fetched).unwrap_err();
+        assert!(err.to_string().contains("was changed on changed since it was fetched"), "{}", err);
+        assert_eq!(std::fs::read_to_string(home.join("list.txt")).unwrap(), "2026-10-16\tFrom elsewhere\n");
+        assert!(!home.join("list.txt.lock").exists());
+    }
+
+    #[test]
+    fn ssh_upload_refuses_while_another_writer_holds_the_lock() {
+        let (file, home) = ssh_host("locked", "2026-10-16\tOld\n");
+        let fetched = file.download().unwrap();
+        std::fs::create_dir(home.join("list.txt.lock")).unwrap();
+        let err = file.upload(b"2026-10-16\tNew\n", &fetched).unwrap_err();
+        assert!(err.to_string().contains("is locked by another writer"), "{}", err);
+        assert_eq!(std::fs::read_to_string(home.join("list.txt")).unwrap(), "2026-10-16\tOld\n");
+        // the other writer's lock is left alone
+        assert!(home.join("list.txt.lock").is_dir());
+    }
+
+    #[test]
+    fn parses_s3_urls() {
+        assert_eq!(
+            S3Object::parse("s3://lists/home/todo.txt"),
+            Some(S3Object { bucket: "lists".to_string(), key: "home/todo.txt".to_string() })
+        );
+        assert_eq!(S3Object::parse("s3://lists/"), None);
+        assert_eq!(S3Object::parse("s3:///todo.txt"), None);
+        assert_eq!(S3Object::parse("s3://lists"), None);
+        assert!(matches!(Remote::parse("s3://lists/todo.txt"), Some(Remote::S3(_))));
+        assert!(matches!(Remote::parse("ssh://box/todo.txt"), Some(Remote::Ssh(_))));
+        assert_eq!(Remote::parse("todo.txt"), None);
+    }
+
+    #[test]
+    fn reads_the_etag_from_the_cli_output() {
+        let printed = r#"{
+    "AcceptRanges": "bytes",
+    "ETag": "\"9b2cf535f27731c974343645a3985328\"",
+    "ContentLength": 42
+}"#;
+        assert_eq!(etag_field(printed), Some(r#""9b2cf535f27731c974343645a3985328""#.to_string()));
+        assert_eq!(etag_field("{\"ContentLength\": 3, \"ETag\":\"abc\"}"), Some("abc".to_string()));
+        assert_eq!(etag_field("{\"ContentLength\": 3}"), None);
+        assert_eq!(etag_field("{\"ETag\": \"unterminated"), None);
+    }
+
+    #[test]
+    fn s3_upload_replaces_an_unchanged_object() {
+        let (object, stored, local) = s3_bucket("upload", "2026-10-16\tOld\n");
+        let fetched = object.download(&local).unwrap();
+        assert_eq!(fetched.content, b"2026-10-16\tOld\n");
+        assert!(fetched.etag.is_some());
+        object.upload(&local, b"2026-10-16\tNew\n", fetched.etag.as_deref()).unwrap();
+        assert_eq!(std::fs::read_to_string(&stored).unwrap(), "2026-10-16\tNew\n");
+        // the temporary download and upload files are gone
+        assert_eq!(std::fs::read_dir(local.parent().unwrap()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn s3_upload_refuses_an_object_changed_since_fetched() {
+        let (object, stored, local) = s3_bucket("changed", "2026-10-16\tOld\n");
+        let fetched = object.download(&local).unwrap();
+        std::fs::write(&stored, "2026-10-16\tFrom elsewhere\n").unwrap();
+        let err = object.upload(&local, b"2026-10-16\tNew\n", fetched.etag.as_deref()).unwrap_err();
+        assert_eq!(err.to_string(), "s3://changed/list.txt was changed since it was fetched; run the command again");
+        assert_eq!(std::fs::read_to_string(&stored).unwrap(), "2026-10-16\tFrom elsewhere\n");
+    }
+
+    #[test]
+    fn s3_download_reports_a_missing_object() {
+        let (object, stored, local) = s3_bucket("missing", "");
+        std::fs::remove_file(&stored).unwrap();
+        let err = object.download(&local).unwrap_err();
+        assert!(err.to_string().contains("NoSuchKey"), "{}", err);
+    }
+}
@@ -0,0 +1,189 @@
+//! Interactive `review`: walk through the entries that need attention one at a time and decide
+//! what to do with each. Changes are collected and written in one go at the end (or on quit).
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use iron_list::audit;
+use iron_list::clock::Clock;
+use iron_list::dates;
+use iron_list::i18n::{Msg, tr, trf};
+use iron_list::{Entry, FileFormat, Status, append_entry, append_to_trash, change_lines, entry_to_line, is_active, next_after_completion, set_status, set_tag_value, sort_file};
+
+/// Why an entry comes up for review.
+#[derive(Clone, Copy)]
+enum Reason {
+    Overdue,
+    Undated,
+    /// Not changed for this many days, according to the audit log
+    Stale(i64),
+}
+
+impl Reason {
+    fn label(self) -> String {
+        match self {
+            Reason::Overdue => tr(Msg::Overdue).to_string(),
+            Reason::Undated => tr(Msg::Undated).to_string(),
+            Reason::Stale(days) => trf(Msg::Untouched, &[&days]),
+        }
+    }
+}
+
+/// Entries that need a decision, in the order they are presented: overdue ones, then those
+/// without a date, then those nobody has changed for more than [`crate::STALE_DAYS`] days.
+/// `written` and `log_start` are what [`audit::last_written`] returned; without a log nothing is
+/// known to be stale.
+fn candidates<'a>(
+    entries: &'a [Entry<'a>],
+    clock: &Clock,
+    written: &HashMap<String, NaiveDate>,
+    log_start: Option<NaiveDate>,
+) -> Vec<(Reason, &'a Entry<'a>)> {
+    let today = clock.today();
+    let mut items: Vec<(Reason, &Entry<'_>)> = entries
+        .iter()
+        .filter(|e| is_active(e))
+        .filter_map(|e| {
+            let untouched = written
+                .get(e.raw_line.as_ref())
+                .copied()
+                .or(log_start)
+                .map(|d| (today - d).num_days())
+                .filter(|&days| days > crate::STALE_DAYS.into());
+            let reason = if e.date == dates::UNDATED {
+                Reason::Undated
+            } else if e.date < clock.today_for(e) {
+                Reason::Overdue
+            } else {
+                Reason::Stale(untouched?)
+            };
+            Some((reason, e))
+        })
+        .collect();
+    // stable, so entries keep list order within each reason
+    items.sort_by_key(|(reason, _)| match reason {
+        Reason::Stale(_) => 2,
+        Reason::Undated => 1,
+        Reason::Overdue => 0,
+    });
+    items
+}
+
+/// Print `question` and read one line of input. Returns `None` at end of input.
+fn prompt(question: &str) -> io::Result<Option<String>> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
+
+pub fn run(path: &Path, entries: &[Entry<'_>], clock: &Clock, sorted_writes: bool, author: Option<&str>) -> io::Result<()> {
+    let (written, log_start) = audit::last_written(path)?;
+    let items = candidates(entries, clock, &written, log_start);
+    if items.is_empty() {
+        println!("{}", tr(Msg::NothingToReview));
+        return Ok(());
+    }
+    println!("{}", trf(Msg::EntriesToReview, &[&items.len()]));
+    let format = FileFormat::of(path);
+
+    let mut changes: Vec<(usize, Option<String>)> = Vec::new();
+    let mut trashed: Vec<&Entry<'_>> = Vec::new();
+    // next occurrences of completed `after:` entries, appended at the end
+    let mut followups: Vec<Entry<'static>> = Vec::new();
+    let (mut rescheduled, mut completed) = (0, 0);
+
+    'entries: for (n, (reason, e)) in items.iter().enumerate() {
+        let Some(line) = e.line else { continue };
+        println!();
+        println!("[{}/{}] {} ({})", n + 1, items.len(), dates::display(e.date), reason.label());
+        println!("  {}", e.desc);
+        if !e.tags.is_empty() {
+            println!("  {}: {}", tr(Msg::TagsLabel), e.tags.join(","));
+        }
+        loop {
+            let Some(answer) = prompt(tr(Msg::ReviewActions))? else {
+                break 'entries;
+            };
+            match answer.to_ascii_lowercase().as_str() {
+                "" | "k" | "keep" => break,
+                "c" | "complete" => {
+                    let mut done = (*e).clone();
+                    crate::stop_tracking(&mut done, clock);
+                    set_status(&mut done, Status::Done);
+                    set_tag_value(&mut done, "done", &dates::store(clock.today()));
+                    if let Some(name) = author {
+                        set_tag_value(&mut done, "done-by", name);
+                    }
+                    changes.push((line, Some(entry_to_line(&done, format))));
+                    followups.extend(next_after_completion(&done, clock.today()));
+                    completed += 1;
+                    break;
+                }
+                "d" | "delete" => {
+                    // deleted entries go to the trash, which a remote list doesn't have
+                    if crate::is_remote() {
+                        println!("{}", trf(Msg::RemoteSidecar, &[&"review delete"]));
+                        continue;
+                    }
+                    changes.push((line, None));
+                    trashed.push(e);
+                    break;
+                }
+                "r" | "reschedule" => {
+                    let date = loop {
+                        let Some(input) = prompt(&trf(Msg::ReviewNewDate, &[&dates::pattern()]))? else {
+                            break 'entries;
+                        };
+                        if input.is_empty() {
+                            break None;
+                        }
+                        match dates::parse_relative(&input, clock.today()) {
+                            Some(date) => break Some(date),
+                            None => println!("{}", trf(Msg::InvalidDate, &[&input])),
+                        }
+                    };
+                    if let Some(date) = date {
+                        let moved = Entry { date, ..(*e).clone() };
+                        changes.push((line, Some(entry_to_line(&moved, format))));
+                        rescheduled += 1;
+                        break;
+                    }
+                }
+                "q" | "quit" => break 'entries,
+                _ => println!("{}", tr(Msg::ReviewAnswerHint)),
+            }
+        }
+    }
+
+    println!();
+    if changes.is_empty() {
+        println!("{}", tr(Msg::NoChanges));
+        return Ok(());
+    }
+    // deleting several entries at once is a bulk change worth a backup
+    if trashed.len() > 1 {
+        crate::backup_before_bulk(path, clock)?;
+    }
+    append_to_trash(path, &trashed, clock.today())?;
+    change_lines(path, &changes)?;
+    for next in &followups {
+        append_entry(path, &entry_to_line(next, format))?;
+    }
+    if sorted_writes && (rescheduled > 0 || !followups.is_empty()) {
+        sort_file(path)?;
+    }
+    println!(
+        "{}",
+        trf(
+            Msg::ReviewSaved,
+            &[&rescheduled, &completed, &trashed.len(), &iron_list::trash_path(path).display()]
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! `serve`: a minimal read-only HTTP server for the iCalendar feed. It answers `GET` (and `HEAD`)
+//! requests for the feed and nothing else, one at a time, rendering the feed afresh for each.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Path the feed is served at.
+pub const FEED_PATH: &str = "/ironlist.ics";
+
+/// Listen for requests for the feed produced by `render`. With `token`, only requests whose
+/// query string carries `token=<token>` get it; every other request is answered with 404.
+/// Runs until the process is stopped.
+pub fn run(listener: TcpListener, token: Option<&str>, render: impl Fn() -> io::Result<String>) {
+    for stream in listener.incoming() {
+        let answered = stream.and_then(|stream| answer(stream, token, &render));
+        if let Err(err) = answered {
+            eprintln!("Could not answer a request: {}", err);
+        }
+    }
+}
+
+fn answer(mut stream: TcpStream, token: Option<&str>, render: impl Fn() -> io::Result<String>) -> io::Result<()> {
+    // a client that never finishes its request must not hold up the others
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let authorized = token.is_none_or(|t| query.split('&').any(|pair| pair.strip_prefix("token=") == Some(t)));
+    let (status, content_type, body) = if method != "GET" && method != "HEAD" {
+        ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string())
+    } else if path != FEED_PATH || !authorized {
+        ("404 Not Found", "text/plain", "Not found\n".to_string())
+    } else {
+        match render() {
+            Ok(feed) => ("200 OK", "text/calendar; charset=utf-8", feed),
+            Err(err) => {
+                eprintln!("Could not render the feed: {}", err);
+                ("500 Internal Server Error", "text/plain", "Could not read the list\n".to_string())
+            }
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
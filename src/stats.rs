@@ -0,0 +1,236 @@
+//! Numbers about a list for `stats`: how many entries are in each state and how fast entries
+//! get done.
+//!
+//! Throughput is read from the `done:YYYY-MM-DD` tag that completing an entry adds; entries
+//! completed before that tag existed have no completion date and are left out of it.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+use crate::clock::Clock;
+use crate::export::json_escape;
+use crate::{Entry, Status, completed_on, dates, status};
+
+/// How many entries are in each state. `overdue`, `due_today` and `undated` are counted among
+/// the `open` ones; `someday` entries are not open.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub open: usize,
+    pub overdue: usize,
+    pub due_today: usize,
+    pub undated: usize,
+    pub someday: usize,
+    pub done: usize,
+    pub cancelled: usize,
+}
+
+pub fn counts(entries: &[Entry<'_>], clock: &Clock) -> Counts {
+    let mut c = Counts::default();
+    for e in entries {
+        match status(e) {
+            Status::Done => c.done += 1,
+            Status::Cancelled => c.cancelled += 1,
+            Status::Someday => c.someday += 1,
+            _ => {
+                c.open += 1;
+                let today = clock.today_for(e);
+                if e.date == dates::UNDATED {
+                    c.undated += 1;
+                } else if e.date < today {
+                    c.overdue += 1;
+                } else if e.date == today {
+                    c.due_today += 1;
+                }
+            }
+        }
+    }
+    c
+}
+
+/// Entries completed in the period starting on `start` (a day, a Monday or the first of a month).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: NaiveDate,
+    pub done: usize,
+}
+
+/// Completed entries per day (the last 7), per week (the last 8) and per month (the last 6),
+/// oldest first and each including the current one, plus averages to compare with what is
+/// scheduled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Velocity {
+    pub days: Vec<Period>,
+    pub weeks: Vec<Period>,
+    pub months: Vec<Period>,
+    /// Entries completed per day on average over the last 7 days.
+    pub avg_7: f64,
+    /// Entries completed per day on average over the last 28 days.
+    pub avg_28: f64,
+    /// Open entries due per day on average over the next 7 days, today included.
+    pub scheduled_7: f64,
+}
+
+pub fn velocity(entries: &[Entry<'_>], today: NaiveDate) -> Velocity {
+    let completed: Vec<NaiveDate> = entries.iter().filter_map(completed_on).filter(|&d| d <= today).collect();
+    let done_between = |from: NaiveDate, to: NaiveDate| completed.iter().filter(|&&d| from <= d && d < to).count();
+    let periods = |starts: Vec<NaiveDate>, end: NaiveDate| -> Vec<Period> {
+        let ends = starts.iter().skip(1).copied().chain([end]);
+        starts.iter().zip(ends).map(|(&start, to)| Period { start, done: done_between(start, to) }).collect()
+    };
+    let tomorrow = today + Duration::days(1);
+
+    let days = periods((0..7).rev().map(|n| today - Duration::days(n)).collect(), tomorrow);
+    let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+    let weeks = periods((0..8).rev().map(|n| monday - Duration::weeks(n)).collect(), tomorrow);
+    let first = today.with_day(1).expect("every month has a first day");
+    let months = periods(
+        (0..6).rev().filter_map(|n| first.checked_sub_months(Months::new(n))).collect(),
+        tomorrow,
+    );
+
+    let scheduled = entries
+        .iter()
+        .filter(|e| !status(e).is_closed() && status(e) != Status::Someday)
+        .filter(|e| today <= e.date && e.date < today + Duration::days(7))
+        .count();
+    Velocity {
+        days,
+        weeks,
+        months,
+        avg_7: done_between(today - Duration::days(6), tomorrow) as f64 / 7.0,
+        avg_28: done_between(today - Duration::days(27), tomorrow) as f64 / 28.0,
+        scheduled_7: scheduled as f64 / 7.0,
+    }
+}
+
+/// Open and done entries carrying one plain tag (`key:value` and status tags are left out).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCounts {
+    pub tag: String,
+    pub open: usize,
+    pub done: usize,
+}
+
+/// Per-tag counts, by tag name (compared case-insensitively, shown in lower case).
+pub fn by_tag(entries: &[Entry<'_>]) -> Vec<TagCounts> {
+    let mut tags: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for e in entries {
+        let st = status(e);
+        for t in e.tags.iter() {
+            if t.contains(':') || ["complete", "in-progress", "waiting"].iter().any(|s| t.eq_ignore_ascii_case(s)) {
+                continue;
+            }
+            let counts = tags.entry(t.to_lowercase()).or_default();
+            match st {
+                Status::Done => counts.1 += 1,
+                Status::Cancelled | Status::Someday => {}
+                _ => counts.0 += 1,
+            }
+        }
+    }
+    tags.into_iter().map(|(tag, (open, done))| TagCounts { tag, open, done }).collect()
+}
+
+/// Open overdue entries by how many days they are late.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Aging {
+    pub days_1_7: usize,
+    pub days_8_30: usize,
+    pub days_31_90: usize,
+    pub over_90: usize,
+}
+
+pub fn overdue_aging(entries: &[Entry<'_>], clock: &Clock) -> Aging {
+    let mut a = Aging::default();
+    for e in entries.iter().filter(|e| !status(e).is_closed() && status(e) != Status::Someday) {
+        let late = (clock.today_for(e) - e.date).num_days();
+        match late {
+            ..=0 => {}
+            1..=7 => a.days_1_7 += 1,
+            8..=30 => a.days_8_30 += 1,
+            31..=90 => a.days_31_90 += 1,
+            _ => a.over_90 += 1,
+        }
+    }
+    a
+}
+
+/// Every metric as one JSON object, for dashboards. The schema is stable: fields may be added
+/// but are never renamed or removed without bumping `schema`.
+pub fn to_json(today: NaiveDate, counts: &Counts, tags: &[TagCounts], aging: &Aging, velocity: &Velocity) -> String {
+    let periods = |periods: &[Period]| -> String {
+        let items: Vec<String> = periods
+            .iter()
+            .map(|p| format!("{{\"start\": \"{}\", \"done\": {}}}", dates::store(p.start), p.done))
+            .collect();
+        format!("[{}]", items.join(", "))
+    };
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|t| format!("{{\"tag\": \"{}\", \"open\": {}, \"done\": {}}}", json_escape(&t.tag), t.open, t.done))
+        .collect();
+
+    let mut out = String::new();
+    writeln!(out, "{{").ok();
+    writeln!(out, "  \"schema\": 1,").ok();
+    writeln!(out, "  \"date\": \"{}\",", dates::store(today)).ok();
+    writeln!(
+        out,
+        "  \"counts\": {{\"open\": {}, \"overdue\": {}, \"due_today\": {}, \"undated\": {}, \"someday\": {}, \"done\": {}, \"cancelled\": {}}},",
+        counts.open, counts.overdue, counts.due_today, counts.undated, counts.someday, counts.done, counts.cancelled
+    )
+    .ok();
+    writeln!(out, "  \"tags\": [{}],", tags.join(", ")).ok();
+    writeln!(
+        out,
+        "  \"overdue_aging\": {{\"days_1_7\": {}, \"days_8_30\": {}, \"days_31_90\": {}, \"over_90\": {}}},",
+        aging.days_1_7, aging.days_8_30, aging.days_31_90, aging.over_90
+    )
+    .ok();
+    writeln!(out, "  \"velocity\": {{").ok();
+    writeln!(out, "    \"days\": {},", periods(&velocity.days)).ok();
+    writeln!(out, "    \"weeks\": {},", periods(&velocity.weeks)).ok();
+    writeln!(out, "    \"months\": {},", periods(&velocity.months)).ok();
+    writeln!(out, "    \"average_7\": {:.2},", velocity.avg_7).ok();
+    writeln!(out, "    \"average_28\": {:.2},", velocity.avg_28).ok();
+    writeln!(out, "    \"scheduled_7\": {:.2}", velocity.scheduled_7).ok();
+    writeln!(out, "  }}").ok();
+    writeln!(out, "}}").ok();
+    out
+}
+
+/// The class a status bar can style by: `overdue`, `due` (something due today) or `clear`.
+pub fn bar_class(counts: &Counts) -> &'static str {
+    if counts.overdue > 0 {
+        "overdue"
+    } else if counts.due_today > 0 {
+        "due"
+    } else {
+        "clear"
+    }
+}
+
+/// A one-line JSON summary for status bars and scripts.
+pub fn bar_json(counts: &Counts, text: &str) -> String {
+    format!(
+        "{{\"text\": \"{}\", \"class\": \"{}\", \"due_today\": {}, \"overdue\": {}, \"open\": {}}}",
+        json_escape(text),
+        bar_class(counts),
+        counts.due_today,
+        counts.overdue,
+        counts.open
+    )
+}
+
+/// Output for a waybar `custom` module with `"return-type": "json"`.
+pub fn waybar_json(counts: &Counts, text: &str, tooltip: &str) -> String {
+    format!(
+        "{{\"text\": \"{}\", \"tooltip\": \"{}\", \"class\": \"{}\", \"alt\": \"{}\"}}",
+        json_escape(text),
+        json_escape(tooltip),
+        bar_class(counts),
+        bar_class(counts)
+    )
+}
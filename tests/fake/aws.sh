@@ -0,0 +1,16 @@
+#!/bin/sh
+op=$2 bucket=$4 key=$6
+shift 8
+object="$(dirname "$0")/buckets/$bucket/$key"
+etag() { cksum < "$object" | cut -d' ' -f1; }
+case $op in
+get-object)
+    [ -f "$object" ] || { echo "An error occurred (NoSuchKey) when calling the GetObject operation" >&2; exit 254; }
+    cp "$object" "$1" && printf '{\n    "ETag": "\\"%s\\"",\n    "ContentLength": 1\n}\n' "$(etag)" ;;
+put-object)
+    body=$2
+    if [ "$3" = "--if-match" ] && [ "$4" != "\"$(etag)\"" ]; then
+        echo "An error occurred (PreconditionFailed) when calling the PutObject operation" >&2; exit 254
+    fi
+    cp "$body" "$object" && printf '{\n    "ETag": "\\"%s\\""\n}\n' "$(etag)" ;;
+esac
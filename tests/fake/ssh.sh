@@ -0,0 +1,4 @@
+#!/bin/sh
+while [ "$1" != "--" ]; do shift; done
+host=$2
+HOME="$(dirname "$0")/hosts/$host" && cd "$HOME" && exec sh -c "$3"
@@ -0,0 +1,73 @@
+//! Runs the binary on an `s3://` list with a stand-in `aws` client (see `fake/aws.sh`), to check
+//! that what a command wrote reaches the object however the command ends.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+/// A fresh directory for test `name` with the stand-in `aws` in `bin/`, the object
+/// `s3://lists/list.txt` holding `content`, and a home directory whose config file is `config`.
+fn setup(name: &str, content: &str, config: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("iron-list-cli-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    let bucket = dir.join("bin/buckets/lists");
+    std::fs::create_dir_all(&bucket).unwrap();
+    std::fs::write(bucket.join("list.txt"), content).unwrap();
+    let aws = dir.join("bin/aws");
+    std::fs::write(&aws, include_str!("fake/aws.sh")).unwrap();
+    std::fs::set_permissions(&aws, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::create_dir_all(dir.join("home")).unwrap();
+    std::fs::write(dir.join("home/.ironlist_config"), config).unwrap();
+    dir
+}
+
+/// Run `iron-list --file s3://lists/list.txt` with `args` in the directory made by [`setup`].
+fn iron_list(dir: &Path, args: &[&str]) -> Output {
+    let path = format!("{}:{}", dir.join("bin").display(), std::env::var("PATH").unwrap_or_default());
+    std::process::Command::new(env!("CARGO_BIN_EXE_iron-list"))
+        .args(["--file", "s3://lists/list.txt", "--no-pager"])
+        .args(args)
+        .current_dir(dir)
+        .env("PATH", path)
+        .env("HOME", dir.join("home"))
+        .env_remove("XDG_CACHE_HOME")
+        .env("LC_ALL", "C")
+        .output()
+        .unwrap()
+}
+
+/// The object as it is stored now.
+fn stored(dir: &Path) -> String {
+    std::fs::read_to_string(dir.join("bin/buckets/lists/list.txt")).unwrap()
+}
+
+#[test]
+fn query_without_matches_uploads_the_auto_rolled_list() {
+    let dir = setup("no-match", "2020-01-02\tOverdue\twork\n", "auto_roll = true\n");
+    let out = iron_list(&dir, &["query", "--tag", "home"]);
+    assert_eq!(out.status.code(), Some(2), "{}", String::from_utf8_lossy(&out.stderr));
+    let list = stored(&dir);
+    assert!(list.contains("\tOverdue\t") && !list.contains("2020-01-02"), "{}", list);
+}
+
+#[test]
+fn failing_command_uploads_the_auto_rolled_list() {
+    let dir = setup("failing", "2020-01-02\tOverdue\twork\n", "auto_roll = true\n");
+    // `query` without criteria fails only after auto_roll rolled the list
+    let out = iron_list(&dir, &["query"]);
+    assert_eq!(out.status.code(), Some(1), "{}", String::from_utf8_lossy(&out.stderr));
+    let list = stored(&dir);
+    assert!(list.contains("\tOverdue\t") && !list.contains("2020-01-02"), "{}", list);
+}
+
+#[test]
+fn sidecar_commands_refuse_a_remote_list() {
+    let dir = setup("sidecar", "2020-01-02\tOverdue\twork\n", "");
+    let out = iron_list(&dir, &["in", "Call the bank"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("`in` is not available for a remote list"));
+    // nothing but the working copy was written next to it
+    let cache = dir.join("home/.cache/iron-list/remote");
+    assert_eq!(std::fs::read_dir(cache).unwrap().count(), 1);
+    assert_eq!(stored(&dir), "2020-01-02\tOverdue\twork\n");
+}